@@ -0,0 +1,398 @@
+//! Passes that inspect parsed routines before they are compiled to objects.
+
+use core::fmt::{Display, Formatter};
+use std::collections::HashMap;
+
+use gdlib::gdobj::Item;
+
+use crate::{
+    core::{
+        consts::{ENTRY_POINT, INIT_ROUTINE},
+        flags::FlagValue,
+        structs::{BuiltinAlias, InstrType, Instruction, Routine, TasmValue},
+        timing::Spawns,
+    },
+    instr::get_item_spec,
+};
+
+/// Instructions whose objects are opaque to the compiler, and may spawn any group.
+const OPAQUE_INSTRUCTIONS: [&str; 2] = ["RAW", "RAWTRG"];
+
+/// Groups that an instruction may spawn, toggle or otherwise refer to.
+fn referenced_groups(instr: &Instruction) -> Vec<i16> {
+    let mut groups = vec![];
+    for arg in &instr.args {
+        match arg {
+            TasmValue::Group(g) => groups.push(*g),
+            // dict arguments map groups to counts or weights
+            TasmValue::Dict(d) => groups.extend(d.iter().map(|(g, _)| *g)),
+            _ => (),
+        }
+    }
+    for flag in &instr.flags {
+        // remaps may go either way
+        if let FlagValue::Dict(d) = &flag.value {
+            groups.extend(d.iter().flat_map(|&(a, b)| [a, b]));
+        }
+    }
+    groups
+}
+
+/// Returns the indices of the routines that can never be spawned.
+/// `_start`, `_init` and exported routines are always reachable, and so is every routine
+/// referenced by a reachable routine. If any routine uses raw objects, nothing is unreachable,
+/// since the raw objects could spawn anything.
+pub fn unreachable_routines(routines: &[Routine]) -> Vec<usize> {
+    let by_group = routines
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.ident != INIT_ROUTINE)
+        .map(|(i, r)| (r.group, i))
+        .collect::<HashMap<_, _>>();
+
+    if routines
+        .iter()
+        .flat_map(|r| &r.instructions)
+        .any(|i| OPAQUE_INSTRUCTIONS.contains(&i.ident.as_str()))
+    {
+        return vec![];
+    }
+
+    let mut reachable = routines
+        .iter()
+        .map(|r| r.ident == ENTRY_POINT || r.ident == INIT_ROUTINE || r.exported)
+        .collect::<Vec<_>>();
+    let mut stack = (0..routines.len())
+        .filter(|&i| reachable[i])
+        .collect::<Vec<_>>();
+
+    while let Some(idx) = stack.pop() {
+        for instr in &routines[idx].instructions {
+            for group in referenced_groups(instr) {
+                if let Some(&target) = by_group.get(&group)
+                    && !reachable[target]
+                {
+                    reachable[target] = true;
+                    stack.push(target);
+                }
+            }
+        }
+    }
+
+    (0..routines.len()).filter(|&i| !reachable[i]).collect()
+}
+
+/// Something that may be written by a routine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resource {
+    Item(Item),
+    /// Items behind a builtin alias, which are only known when compiling
+    Alias(BuiltinAlias),
+    /// The memory block, including its pointer and register
+    Memory,
+    /// The memory pointer, which is only used by checks within a routine
+    Pointer,
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Item(Item::Counter(c)) => write!(f, "C{c}"),
+            Self::Item(Item::Timer(t)) => write!(f, "T{t}"),
+            Self::Item(i) => write!(f, "{i:?}"),
+            Self::Alias(a) => write!(f, "{a:?}"),
+            Self::Memory => write!(f, "memory"),
+            Self::Pointer => write!(f, "the memory pointer"),
+        }
+    }
+}
+
+/// Two routines that may write the same resource at the same time.
+#[derive(Debug, Clone)]
+pub struct DataRace {
+    pub resource: Resource,
+    /// Index of each routine, with the line of its first write
+    pub first: (usize, usize),
+    pub second: (usize, usize),
+}
+
+/// Resources written by a routine, with the line of the first write to each of them.
+fn written_resources(routine: &Routine) -> Vec<(Resource, usize)> {
+    let mut writes: Vec<(Resource, usize)> = vec![];
+    for instr in &routine.instructions {
+        let resource = match (instr.itype, instr.args.first()) {
+            (InstrType::Memory, _) => Resource::Memory,
+            (InstrType::Arithmetic, Some(TasmValue::Alias(a))) => Resource::Alias(*a),
+            (InstrType::Arithmetic, Some(target)) => match get_item_spec(target) {
+                Some(item) => Resource::Item(item),
+                None => continue,
+            },
+            _ => continue,
+        };
+        if !writes.iter().any(|(r, _)| *r == resource) {
+            writes.push((resource, instr.line_number));
+        }
+    }
+    writes
+}
+
+/// Every routine that may run while the given one does, found from the spawn graph alone.
+/// A routine runs alongside everything it spawns before its last instruction, and everything
+/// spawned by separate instructions of the same routine runs alongside each other.
+/// Routines spawned by events may run alongside anything.
+fn concurrent_routines(routines: &[Routine]) -> Vec<Vec<bool>> {
+    let by_group = routines
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.ident != INIT_ROUTINE)
+        .map(|(i, r)| (r.group, i))
+        .collect::<HashMap<_, _>>();
+    let to_routines = |groups: &[i16]| {
+        groups
+            .iter()
+            .filter_map(|g| by_group.get(g).copied())
+            .collect::<Vec<_>>()
+    };
+
+    // every routine that is spawned by a routine, including itself
+    let descendants = (0..routines.len())
+        .map(|root| {
+            let mut seen = vec![false; routines.len()];
+            seen[root] = true;
+            let mut stack = vec![root];
+            while let Some(idx) = stack.pop() {
+                for instr in &routines[idx].instructions {
+                    for target in to_routines(Spawns::new(instr.itype, &instr.args).targets()) {
+                        if !seen[target] {
+                            seen[target] = true;
+                            stack.push(target);
+                        }
+                    }
+                }
+            }
+            (0..routines.len()).filter(|&i| seen[i]).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut concurrent = vec![vec![false; routines.len()]; routines.len()];
+    let mut mark = |a: &[usize], b: &[usize]| {
+        for &x in a {
+            for &y in b {
+                if x != y {
+                    concurrent[x][y] = true;
+                    concurrent[y][x] = true;
+                }
+            }
+        }
+    };
+
+    for (idx, routine) in routines.iter().enumerate() {
+        // (descendants of every target, whether only one target is spawned)
+        let mut spawned: Vec<(Vec<Vec<usize>>, bool)> = vec![];
+        for (i, instr) in routine.instructions.iter().enumerate() {
+            let spawns = Spawns::new(instr.itype, &instr.args);
+            let targets = to_routines(spawns.targets())
+                .into_iter()
+                .map(|t| descendants[t].clone())
+                .collect::<Vec<_>>();
+
+            if instr.itype == InstrType::Event {
+                let every = (0..routines.len())
+                    .filter(|&i| routines[i].ident != INIT_ROUTINE)
+                    .collect::<Vec<_>>();
+                for t in to_routines(&referenced_groups(instr)) {
+                    mark(&descendants[t], &every);
+                }
+                continue;
+            }
+
+            // the routine has finished by the time its last instruction spawns anything
+            if routine.ident != INIT_ROUTINE && i + 1 < routine.instructions.len() {
+                for t in &targets {
+                    mark(&[idx], t);
+                }
+            }
+            spawned.push((targets, matches!(spawns, Spawns::OneOf(_))));
+        }
+
+        for (i, (targets, one_of)) in spawned.iter().enumerate() {
+            for (j, a) in targets.iter().enumerate() {
+                if !one_of {
+                    for b in &targets[j + 1..] {
+                        mark(a, b);
+                    }
+                }
+                for (other, _) in &spawned[i + 1..] {
+                    for b in other {
+                        mark(a, b);
+                    }
+                }
+            }
+        }
+    }
+    concurrent
+}
+
+/// Returns every pair of routines that may run at the same time, and write the same resource.
+/// The init routine runs before everything else, so it never races.
+pub fn data_races(routines: &[Routine]) -> Vec<DataRace> {
+    let concurrent = concurrent_routines(routines);
+    let writes = routines.iter().map(written_resources).collect::<Vec<_>>();
+
+    let mut races = vec![];
+    for a in 0..routines.len() {
+        for b in (a + 1)..routines.len() {
+            if !concurrent[a][b] {
+                continue;
+            }
+            for &(resource, line_a) in &writes[a] {
+                if let Some(&(_, line_b)) = writes[b].iter().find(|(r, _)| *r == resource) {
+                    races.push(DataRace {
+                        resource,
+                        first: (a, line_a),
+                        second: (b, line_b),
+                    });
+                }
+            }
+        }
+    }
+    races
+}
+
+/// Instructions that move the memory pointer.
+const POINTER_MOVES: [&str; 2] = ["LMPTR", "LMRESET"];
+/// Instructions that allocate new memory, with the register and pointer as the last two counters.
+const NEW_MEMORY: [&str; 2] = ["MALLOC", "FMALLOC"];
+
+/// A concurrent chain that may not behave the same way every time it is executed.
+#[derive(Debug, Clone)]
+pub struct ChainIssue {
+    /// Lines of the first and last instruction of the chain
+    pub lines: (usize, usize),
+    pub details: String,
+}
+
+/// Maps the items behind the memory pointer and register onto the resources used by memory.
+struct MemoryItems {
+    pointer: Option<Item>,
+    register: Option<Item>,
+}
+
+impl MemoryItems {
+    fn new(routines: &[Routine]) -> Self {
+        let end = routines
+            .iter()
+            .flat_map(|r| &r.instructions)
+            .find(|i| NEW_MEMORY.contains(&i.ident.as_str()))
+            .and_then(|i| i.args.get(1)?.to_int());
+        Self {
+            pointer: end.map(|e| Item::Counter(e as i16)),
+            register: end.map(|e| Item::Counter(e as i16 - 1)),
+        }
+    }
+
+    fn resource(&self, value: &TasmValue) -> Option<Resource> {
+        match value {
+            TasmValue::Alias(BuiltinAlias::PTRPOS) => Some(Resource::Pointer),
+            TasmValue::Alias(a) => Some(Resource::Alias(*a)),
+            v => match get_item_spec(v)? {
+                item if Some(item) == self.pointer => Some(Resource::Pointer),
+                item if Some(item) == self.register => Some(Resource::Alias(BuiltinAlias::MEMREG)),
+                item => Some(Resource::Item(item)),
+            },
+        }
+    }
+
+    /// Resources read and written by an instruction.
+    fn accesses(&self, instr: &Instruction) -> (Vec<Resource>, Vec<Resource>) {
+        let register = Resource::Alias(BuiltinAlias::MEMREG);
+        if POINTER_MOVES.contains(&instr.ident.as_str()) {
+            return (vec![Resource::Pointer], vec![Resource::Pointer]);
+        }
+        match instr.itype {
+            InstrType::Memory => (
+                vec![Resource::Memory, Resource::Pointer, register],
+                vec![Resource::Memory, register],
+            ),
+            InstrType::Arithmetic => {
+                let target = self.resource(&instr.args[0]);
+                let mut reads = instr.args[1..]
+                    .iter()
+                    .filter_map(|a| self.resource(a))
+                    .collect::<Vec<_>>();
+                if instr.ident != "MOV" {
+                    reads.extend(target);
+                }
+                (reads, target.into_iter().collect())
+            }
+            _ => (
+                instr.args.iter().filter_map(|a| self.resource(a)).collect(),
+                vec![],
+            ),
+        }
+    }
+}
+
+/// Checks every chain of `~` instructions in the routine for operations that depend on
+/// the order in which they are executed, and for chains that end in a wait.
+pub fn concurrent_chain_issues(routines: &[Routine], routine: &Routine) -> Vec<ChainIssue> {
+    let memory = MemoryItems::new(routines);
+    let instructions = &routine.instructions;
+    let mut issues = vec![];
+
+    let mut start = 0;
+    while start < instructions.len() {
+        let mut end = start;
+        while instructions.get(end + 1).is_some_and(|i| i.is_concurrent) {
+            end += 1;
+        }
+        let chain = &instructions[start..=end];
+        start = end + 1;
+        if chain.len() < 2 {
+            continue;
+        }
+        let lines = (chain[0].line_number, chain[chain.len() - 1].line_number);
+
+        let accesses = chain.iter().map(|i| memory.accesses(i)).collect::<Vec<_>>();
+        let mut reported: Vec<Resource> = vec![];
+        for (a, (_, writes)) in accesses.iter().enumerate() {
+            for resource in writes {
+                if reported.contains(resource) {
+                    continue;
+                }
+                // any other instruction that reads or writes what this one writes
+                let Some(b) = (0..chain.len()).find(|&b| {
+                    b != a && (accesses[b].0.contains(resource) || accesses[b].1.contains(resource))
+                }) else {
+                    continue;
+                };
+                reported.push(*resource);
+                let details = match resource {
+                    Resource::Pointer => format!(
+                        "The memory pointer is moved on line {} while line {} uses it on the same tick.",
+                        chain[a].line_number + 1,
+                        chain[b].line_number + 1
+                    ),
+                    _ => format!(
+                        "{} is written on line {} while line {} uses it on the same tick.",
+                        resource,
+                        chain[a].line_number + 1,
+                        chain[b].line_number + 1
+                    ),
+                };
+                issues.push(ChainIssue { lines, details });
+            }
+        }
+
+        // the delay of a chain is the delay of its last instruction
+        if chain[chain.len() - 1].itype == InstrType::Wait {
+            issues.push(ChainIssue {
+                lines,
+                details:
+                    "The chain ends in a wait, which replaces the delay of the rest of the chain."
+                        .into(),
+            });
+        }
+    }
+    issues
+}
@@ -0,0 +1,99 @@
+//! Machine-readable output of errors and warnings.
+//!
+//! In the JSON format, every diagnostic is written as a single line of JSON, followed by a
+//! summary line, so that tools can read the output of the compiler line by line.
+
+use core::{fmt::Display, str::FromStr};
+
+use crate::core::{
+    error::{NO_ROUTINE, TasmError},
+    lint::Lint,
+    sourcemap::escape_json,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MessageFormat {
+    /// Colored messages with the offending source line
+    #[default]
+    Human,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "Unknown message format {s}. Expected human or json."
+            )),
+        }
+    }
+}
+
+impl Display for MessageFormat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Human => write!(f, "human"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+fn json_str(s: Option<&str>) -> String {
+    s.map_or("null".into(), |s| format!("\"{}\"", escape_json(s)))
+}
+
+fn json_num(n: Option<usize>) -> String {
+    n.map_or("null".into(), |n| n.to_string())
+}
+
+/// Serialises a single error or warning to a line of JSON.
+/// Lines and columns start from 1, and are `null` if the diagnostic does not point at the source.
+/// The routine is `null` if the diagnostic is not in a routine.
+pub fn diagnostic_json(e: &TasmError) -> String {
+    let severity = match e.error {
+        true => "error",
+        false => "warning",
+    };
+    // the end column is exclusive, like the underline ends right before it
+    let (column, end_column) = match &e.span {
+        Some(s) => (
+            Some(s.column()),
+            Some(s.column() + s.source[s.start..s.end].chars().count()),
+        ),
+        None => (None, None),
+    };
+    format!(
+        r#"{{"kind": "diagnostic", "type": "{:?}", "severity": "{severity}", "lint": {}, "file": "{}", "routine": {}, "line": {}, "column": {}, "end_column": {}, "message": "{}", "help": {}}}"#,
+        e.etype,
+        json_str(Lint::from_etype(e.etype).map(Lint::name)),
+        escape_json(&e.file),
+        json_str((!e.routine.is_empty() && e.routine != NO_ROUTINE).then_some(e.routine.as_str())),
+        json_num(e.source_line().map(|l| l + 1)),
+        json_num(column),
+        json_num(end_column),
+        escape_json(&e.details),
+        json_str(e.help.as_deref())
+    )
+}
+
+/// Serialises the summary of a compilation to a line of JSON.
+pub fn summary_json(file: &str, errors: usize, warnings: usize) -> String {
+    format!(
+        r#"{{"kind": "summary", "file": "{}", "success": {}, "errors": {errors}, "warnings": {warnings}}}"#,
+        escape_json(file),
+        errors == 0
+    )
+}
+
+/// Prints every error and warning as JSON, followed by the summary.
+pub fn print_json(file: &str, errors: &[TasmError], warnings: &[TasmError]) {
+    for e in errors.iter().chain(warnings) {
+        println!("{}", diagnostic_json(e));
+    }
+    println!("{}", summary_json(file, errors.len(), warnings.len()));
+}
@@ -1,4 +1,15 @@
-use core::{error::Error, fmt::Display};
+use core::{
+    error::Error,
+    fmt::{Display, Write},
+};
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+/// Routine of errors that are not in any routine, like bad tokens before the first label.
+pub const NO_ROUTINE: &str = "<No routine>";
 
 /// Representative of TASM high-level lexer, parser, and logic errors.
 ///
@@ -7,6 +18,7 @@ use core::{error::Error, fmt::Display};
 ///     - In the future, this could also include modules and imported files.
 /// - `routine`: the routine in which the error occurred. This is typically the current routine being compiled.
 /// - `line`: the line number in which the error occurred. This is typically the line number in the source file being compiled. 0 if the error does not use a line (like `ExceedsGroupLimit`).
+/// - `span`: the part of the line that the error points at, if it is known.
 /// - `details`: a detailed message about the error. This is typically a human-readable message that provides more information about the error.
 #[derive(Debug, Clone)]
 pub struct TasmError {
@@ -15,10 +27,38 @@ pub struct TasmError {
     pub routine: String, // routine (helps with navigation)
     pub error: bool,     // warning: false
     pub line: usize,     // 0 if doesnt use a line (like ExceedsGroupLimit)
-    pub details: String, // details msg
+    pub span: Option<Box<Span>>,
+    pub details: String,      // details msg
+    pub help: Option<String>, // suggested fix
+}
+
+/// Columns of a source line that an error points at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    /// The source line, without comments and with tabs converted to spaces
+    pub source: Box<str>,
+    /// Byte offset of the first column
+    pub start: usize,
+    /// Byte offset after the last column
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(source: &str, start: usize, end: usize) -> Self {
+        Self {
+            source: source.into(),
+            start,
+            end,
+        }
+    }
+
+    /// Column of the start of the span, starting at 1 like in most editors.
+    pub fn column(&self) -> usize {
+        self.source[..self.start].chars().count() + 1
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 #[non_exhaustive]
 pub enum TasmErrorType {
     InvalidInstruction,
@@ -43,7 +83,22 @@ pub enum TasmErrorType {
     InvalidPointerMove,
     InitRoutineMemoryAccess,
     NonexistentMemoryAccess,
+    MultipleConsoleInstances,
+    NonexistentConsoleAccess,
+    MultipleFramebufferInstances,
+    NonexistentFramebufferAccess,
     TrailingComma,
+    UnknownEvent,
+    UnreachableRoutine,
+    DataRace,
+    ConcurrentChain,
+    UnusedAlias,
+    UnusedDisplay,
+    EmptyRoutineSpawn,
+    ShadowedCounter,
+    BadPragma,
+    UnknownLint,
+    OverlappingItems,
 }
 
 impl Error for TasmError {
@@ -52,22 +107,104 @@ impl Error for TasmError {
     }
 }
 
-impl Display for TasmError {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        if self.line != 0 {
-            write!(
-                f,
-                "{} @ {}:{} [{:?}] {}",
+impl TasmError {
+    /// Error on the given line of an instruction. The file and routine are filled in by the caller.
+    pub fn at_line<T: Into<String>>(etype: TasmErrorType, line: usize, details: T) -> Self {
+        Self {
+            etype,
+            file: String::new(),
+            routine: String::new(),
+            error: true,
+            line,
+            span: None,
+            details: details.into(),
+            help: None,
+        }
+    }
+
+    /// Points the error at the given part of its line.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(Box::new(span));
+        self
+    }
+
+    pub fn with_help<T: Into<String>>(mut self, help: T) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Line the error points at, if any. Line 0 is only pointed at by errors with a span, since
+    /// errors that do not use a line also have line 0.
+    pub fn source_line(&self) -> Option<usize> {
+        (self.line != 0 || self.span.is_some()).then_some(self.line)
+    }
+
+    fn header(&self, color: bool) -> String {
+        let etype = paint(color, self.accent(), &format!("[{:?}]", self.etype));
+        if let Some(line) = self.source_line() {
+            // line + 1 to match the visual index, e.g. line 0 appears as line 1 in most editors
+            let column = self
+                .span
+                .as_ref()
+                .map_or(String::new(), |s| format!(":{}", s.column()));
+            format!(
+                "{} @ {}:{}{column} {etype} {}",
                 self.file,
                 self.routine,
-                // line + 1 to match the visual index, e.g. line 0 appears as line 1 in most editors
-                self.line + 1,
-                self.etype,
+                line + 1,
                 self.details
             )
         } else {
-            write!(f, "{} [{:?}] {}", self.file, self.etype, self.details)
+            format!("{} {etype} {}", self.file, self.details)
+        }
+    }
+
+    fn accent(&self) -> &'static str {
+        match self.error {
+            true => RED,
+            false => YELLOW,
+        }
+    }
+
+    /// Formats the error along with the offending source line, where the span is underlined.
+    /// Colors are only used if `color` is set, e.g. when printing to a terminal.
+    pub fn render(&self, color: bool) -> String {
+        let mut out = self.header(color);
+        let line_no = (self.line + 1).to_string();
+        let pad = match self.span {
+            Some(_) => " ".repeat(line_no.len()),
+            None => String::new(),
+        };
+
+        if let Some(span) = &self.span {
+            let bar = paint(color, BLUE, "|");
+            let underline = "^".repeat(span.source[span.start..span.end].chars().count().max(1));
+            let _ = write!(
+                out,
+                "\n{pad} {bar}\n{} {bar} {}\n{pad} {bar} {}{}",
+                paint(color, BLUE, &line_no),
+                span.source,
+                " ".repeat(span.column() - 1),
+                paint(color, self.accent(), &underline)
+            );
         }
+        if let Some(help) = &self.help {
+            let _ = write!(out, "\n{pad} {} {help}", paint(color, BLUE, "= help:"));
+        }
+        out
+    }
+}
+
+fn paint(color: bool, code: &str, text: &str) -> String {
+    match color {
+        true => format!("{code}{text}{RESET}"),
+        false => text.into(),
+    }
+}
+
+impl Display for TasmError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.header(false))
     }
 }
 
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use gdlib::gdobj::triggers::{Op, RoundMode, SignMode};
+use gdlib::gdobj::triggers::{CompareOp, ItemAlign, Op, RoundMode, SignMode};
 
 #[derive(Debug, Clone)]
 pub struct Flag {
@@ -29,18 +29,24 @@ impl Flag {
 pub enum FlagValue {
     RoundSign((RoundMode, SignMode)),
     Float(f64),
+    Int(i32),
     Op(Op),
+    // comparison operators are parsed from the same flags as arithmetic operators
+    Compare(CompareOp),
     Dict(Vec<(i16, i16)>),
     Bool(bool),
+    Align(ItemAlign),
 }
 
 #[derive(Debug, Clone)]
 pub enum FlagValueType {
     RoundSign,
     Float,
+    Int,
     Op,
     Dict,
     Bool,
+    Align,
 }
 
 fn string_to_roundsign(s: &str) -> FlagValue {
@@ -93,6 +99,11 @@ impl From<FlagValue> for f64 {
         val.to_float().unwrap()
     }
 }
+impl From<FlagValue> for i32 {
+    fn from(val: FlagValue) -> Self {
+        val.to_int().unwrap()
+    }
+}
 impl From<FlagValue> for bool {
     fn from(val: FlagValue) -> Self {
         val.to_bool().unwrap()
@@ -108,6 +119,11 @@ impl From<FlagValue> for Vec<(i16, i16)> {
         val.to_cloned_dict().unwrap()
     }
 }
+impl From<FlagValue> for ItemAlign {
+    fn from(val: FlagValue) -> Self {
+        val.to_align().unwrap()
+    }
+}
 impl From<FlagValue> for (RoundMode, SignMode) {
     fn from(val: FlagValue) -> Self {
         val.to_roundsign().unwrap()
@@ -133,62 +149,29 @@ impl FlagValue {
                 }
                 Err(_) => None,
             },
+            FlagValueType::Int => value.parse::<i32>().ok().map(Self::Int),
             FlagValueType::Op => match value {
                 "+" => Some(Self::Op(Op::Add)),
                 "-" => Some(Self::Op(Op::Sub)),
                 "*" => Some(Self::Op(Op::Mul)),
                 "/" => Some(Self::Op(Op::Div)),
+                "=" | "==" => Some(Self::Compare(CompareOp::Equals)),
+                ">" => Some(Self::Compare(CompareOp::Greater)),
+                "<" => Some(Self::Compare(CompareOp::Less)),
                 _ => None,
             },
-            FlagValueType::Dict => {
-                let mut invalid_dict = false;
-                let resolve_int = |s: &str| -> Option<i16> {
-                    if s.starts_with("0x") {
-                        i16::from_str_radix(&s[2..], 16)
-                    } else {
-                        s.parse::<i16>()
-                    }
-                    .ok()
-                    .or_else(|| group_map.get(s).copied())
-                };
-
-                let parse_int = |s: &str, invalid_dict: &mut bool| -> i16 {
-                    resolve_int(s)
-                        .or_else(|| aliases.get(s).and_then(|a| resolve_int(a)))
-                        .unwrap_or_else(|| {
-                            *invalid_dict = true;
-                            0
-                        })
-                };
-                let kv_pairs: Vec<(i16, i16)> = value[1..value.len() - 1]
-                    .split(',')
-                    .map(|kv| {
-                        let mut split = kv.trim().split(':');
-
-                        let key = parse_int(split.next().unwrap(), &mut invalid_dict);
-                        let value = match split.next() {
-                            Some(v) => parse_int(v, &mut invalid_dict),
-                            None => {
-                                invalid_dict = true;
-                                0
-                            }
-                        };
-
-                        (key, value)
-                    })
-                    .collect::<Vec<_>>();
-
-                if invalid_dict {
-                    None
-                } else {
-                    Some(Self::Dict(kv_pairs))
-                }
-            }
+            FlagValueType::Dict => parse_dict(value, group_map, aliases).map(Self::Dict),
             FlagValueType::Bool => match value {
                 "true" => Some(Self::Bool(true)),
                 "false" => Some(Self::Bool(false)),
                 _ => None,
             },
+            FlagValueType::Align => match value {
+                "left" => Some(Self::Align(ItemAlign::Left)),
+                "center" => Some(Self::Align(ItemAlign::Center)),
+                "right" => Some(Self::Align(ItemAlign::Right)),
+                _ => None,
+            },
         }
     }
 
@@ -196,9 +179,11 @@ impl FlagValue {
         match self {
             Self::Bool(_) => FlagValueType::Bool,
             Self::Dict(_) => FlagValueType::Dict,
-            Self::Op(_) => FlagValueType::Op,
+            Self::Op(_) | Self::Compare(_) => FlagValueType::Op,
             Self::Float(_) => FlagValueType::Float,
+            Self::Int(_) => FlagValueType::Int,
             Self::RoundSign(_) => FlagValueType::RoundSign,
+            Self::Align(_) => FlagValueType::Align,
         }
     }
 
@@ -208,6 +193,12 @@ impl FlagValue {
             _ => None,
         }
     }
+    pub fn to_int(&self) -> Option<i32> {
+        match self {
+            Self::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
     pub fn to_dict(&self) -> Option<&Vec<(i16, i16)>> {
         match self {
             Self::Dict(d) => Some(d),
@@ -244,24 +235,120 @@ impl FlagValue {
             _ => None,
         }
     }
+    pub fn to_align(&self) -> Option<ItemAlign> {
+        match self {
+            Self::Align(a) => Some(*a),
+            _ => None,
+        }
+    }
+    pub fn to_compare_op(&self) -> Option<CompareOp> {
+        match self {
+            Self::Compare(f) => Some(*f),
+            _ => None,
+        }
+    }
 }
 
+/// Parses a dict delimited by braces, like `{1:2, 3:4}`, or a list delimited by brackets,
+/// like `[1, 3]`, which is a dict where every value is 1.
+///
+/// Keys and values may be ints, hex ints, group literals, routine identifiers or aliases
+/// for any of those. Routine identifiers are coerced to their group ID.
+pub fn parse_dict(
+    value: &str,
+    group_map: &HashMap<String, i16>,
+    aliases: &HashMap<String, String>,
+) -> Option<Vec<(i16, i16)>> {
+    let (inner, is_list) = match value.strip_prefix('[') {
+        Some(list) => (list.strip_suffix(']')?, true),
+        None => (value.strip_prefix('{')?.strip_suffix('}')?, false),
+    };
+
+    let resolve_int = |s: &str| -> Option<i16> {
+        let literal = if let Some(hex) = s.strip_prefix("0x") {
+            i16::from_str_radix(hex, 16).ok()
+        } else if let Some(group) = s.strip_prefix('g')
+            && let Ok(id) = group.parse::<i16>()
+        {
+            Some(id)
+        } else {
+            s.parse::<i16>().ok()
+        };
+        literal.or_else(|| group_map.get(s).copied())
+    };
+    let parse_int = |s: &str| -> Option<i16> {
+        let s = s.trim();
+        // the text before the first label is mapped to the empty routine name, which is not a key
+        if s.is_empty() {
+            return None;
+        }
+        resolve_int(s).or_else(|| aliases.get(s).and_then(|a| resolve_int(a)))
+    };
+
+    if inner.trim().is_empty() {
+        return Some(vec![]);
+    }
+    if is_list {
+        return inner.split(',').map(|k| Some((parse_int(k)?, 1))).collect();
+    }
+    inner
+        .split(',')
+        .map(|kv| {
+            let mut split = kv.trim().split(':');
+            let key = parse_int(split.next()?)?;
+            let value = parse_int(split.next()?)?;
+            // only one colon per pair
+            match split.next() {
+                Some(_) => None,
+                None => Some((key, value)),
+            }
+        })
+        .collect()
+}
+
+/// Every flag, and the type of its value.
+const FLAG_TYPES: [(&str, FlagValueType); 31] = [
+    ("resmode", FlagValueType::RoundSign),
+    ("finmode", FlagValueType::RoundSign),
+    ("itemmod", FlagValueType::Float),
+    ("divmod", FlagValueType::Bool),
+    ("iter", FlagValueType::Op),
+    ("op", FlagValueType::Op),
+    ("delay", FlagValueType::Float),
+    ("remap", FlagValueType::Dict),
+    ("ordered", FlagValueType::Bool),
+    ("noremap", FlagValueType::Bool),
+    ("tpaused", FlagValueType::Bool),
+    ("tmod", FlagValueType::Float),
+    ("tstop", FlagValueType::Bool),
+    ("nover", FlagValueType::Bool),
+    ("multi", FlagValueType::Bool),
+    ("instant", FlagValueType::Bool),
+    ("dynamic", FlagValueType::Bool),
+    ("p1", FlagValueType::Bool),
+    ("p2", FlagValueType::Bool),
+    ("fadein", FlagValueType::Float),
+    ("fadeout", FlagValueType::Float),
+    ("uid", FlagValueType::Int),
+    ("channel", FlagValueType::Int),
+    ("loop", FlagValueType::Bool),
+    ("interval", FlagValueType::Float),
+    ("reset", FlagValueType::Float),
+    ("stepreset", FlagValueType::Bool),
+    ("xpos", FlagValueType::Float),
+    ("ypos", FlagValueType::Float),
+    ("align", FlagValueType::Align),
+    ("timefmt", FlagValueType::Bool),
+];
+
 pub fn get_flag_type(ident: &str) -> Option<FlagValueType> {
-    Some(match ident {
-        "resmode" => FlagValueType::RoundSign,
-        "finmode" => FlagValueType::RoundSign,
-        "itemmod" => FlagValueType::Float,
-        "divmod" => FlagValueType::Bool,
-        "iter" => FlagValueType::Op,
-        "op" => FlagValueType::Op,
-        "delay" => FlagValueType::Float,
-        "remap" => FlagValueType::Dict,
-        "ordered" => FlagValueType::Bool,
-        "noremap" => FlagValueType::Bool,
-        "tpaused" => FlagValueType::Bool,
-        "tmod" => FlagValueType::Float,
-        "tstop" => FlagValueType::Bool,
-        "nover" => FlagValueType::Bool,
-        _ => return None,
-    })
+    FLAG_TYPES
+        .iter()
+        .find(|(name, _)| *name == ident)
+        .map(|(_, t)| t.clone())
+}
+
+/// Identifiers of every flag.
+pub fn flag_idents() -> impl Iterator<Item = &'static str> {
+    FLAG_TYPES.iter().map(|(name, _)| *name)
 }
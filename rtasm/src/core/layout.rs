@@ -0,0 +1,202 @@
+//! Placement of routines in the level.
+//!
+//! Routines may be split into rows. Every row after the first one is put in its own group,
+//! which is spawned from the end of the row before it, one tick before the row has to start.
+//! This way, every instruction executes on the same tick as it would on a single line.
+
+use core::{fmt::Display, str::FromStr};
+
+use crate::core::{consts::INIT_ROUTINE, structs::Routine};
+
+/// X-position of the first instruction of a routine, relative to its marker.
+const ROUTINE_START_X: f64 = 105.0;
+const LINE_HEIGHT: f64 = 30.0;
+/// Space between the longest row of a column and the markers of the next column.
+const COLUMN_MARGIN: f64 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LayoutMode {
+    /// Every routine on a single line, at a height given by its group
+    #[default]
+    Linear,
+    /// Routines split into rows of at most N instructions, stacked on top of each other
+    Wrapped(usize),
+    /// Like wrapped, but routines are packed into columns to form a square
+    Grid(usize),
+}
+
+impl FromStr for LayoutMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mode, row_len) = match s.split_once(':') {
+            Some((mode, n)) => (mode, Some(n)),
+            None => (s, None),
+        };
+        let parse_row_len = || match row_len.map(str::parse::<usize>) {
+            Some(Ok(n)) if n > 0 => Ok(n),
+            _ => Err(format!(
+                "The {mode} layout needs a row length above 0, e.g. {mode}:16."
+            )),
+        };
+        match mode {
+            "linear" if row_len.is_none() => Ok(Self::Linear),
+            "wrapped" => Ok(Self::Wrapped(parse_row_len()?)),
+            "grid" => Ok(Self::Grid(parse_row_len()?)),
+            _ => Err(format!(
+                "Unknown layout {s}. Expected linear, wrapped:<N> or grid:<N>."
+            )),
+        }
+    }
+}
+
+impl Display for LayoutMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Linear => write!(f, "linear"),
+            Self::Wrapped(n) => write!(f, "wrapped:{n}"),
+            Self::Grid(n) => write!(f, "grid:{n}"),
+        }
+    }
+}
+
+/// Position and group of a row of instructions.
+#[derive(Debug, Clone, Copy)]
+pub struct Row {
+    /// X-position that the start of the routine would have on this row
+    pub x: f64,
+    pub y: f64,
+    pub group: i16,
+}
+
+impl Row {
+    /// X-position of the object at the given distance from the start of the routine.
+    pub fn pos_x(&self, obj_pos: f64) -> f64 {
+        self.x + obj_pos
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub mode: LayoutMode,
+    /// Lines reserved for routines, above the IOBlock row
+    lines: usize,
+    /// X-position of the markers of the current column
+    column_x: f64,
+    /// Next free line of the current column
+    next_line: usize,
+    /// Largest x-position of any instruction so far
+    right_edge: f64,
+}
+
+impl Layout {
+    pub fn new(mode: LayoutMode, routines: &[Routine], routine_count: usize) -> Self {
+        let rows = |n| {
+            routines
+                .iter()
+                .filter(|r| r.ident != INIT_ROUTINE)
+                .map(move |r| planned_rows(r, n))
+        };
+        let lines = match mode {
+            LayoutMode::Linear => routine_count,
+            LayoutMode::Wrapped(n) => rows(n).sum(),
+            // the longest routine has to fit into a single column
+            LayoutMode::Grid(n) => {
+                let square = (rows(n).sum::<usize>() as f64).sqrt().ceil() as usize;
+                rows(n).max().unwrap_or(0).max(square)
+            }
+        };
+        Self {
+            mode,
+            lines,
+            column_x: 0.0,
+            next_line: 0,
+            right_edge: 0.0,
+        }
+    }
+
+    /// Height of the area above the IOBlock row that is taken up by routines.
+    pub fn routines_height(&self) -> f64 {
+        self.lines as f64 * LINE_HEIGHT
+    }
+
+    /// Maximum amount of instructions on a row, if routines are split into rows.
+    pub fn row_len(&self) -> Option<usize> {
+        match self.mode {
+            LayoutMode::Linear => None,
+            LayoutMode::Wrapped(n) | LayoutMode::Grid(n) => Some(n),
+        }
+    }
+
+    /// Reserves the lines of a routine, and returns the position of its marker.
+    pub fn place_routine(&mut self, routine: &Routine, group_offset: i16) -> (f64, f64) {
+        let row_len = match self.row_len() {
+            // the init routine is placed before the x=0 line, away from other routines
+            Some(n) if routine.ident != INIT_ROUTINE => n,
+            // subtracting from group offset ensures that high group IDs are still placed close to y=0
+            _ => {
+                return (
+                    0.0,
+                    75.0 + ((routine.group - group_offset) as f64) * LINE_HEIGHT,
+                );
+            }
+        };
+
+        let rows = planned_rows(routine, row_len);
+        if matches!(self.mode, LayoutMode::Grid(_))
+            && self.next_line > 0
+            && self.next_line + rows > self.lines
+        {
+            self.column_x = self.right_edge + COLUMN_MARGIN;
+            self.next_line = 0;
+        }
+        // first line is right above the IOBlock row, like in the linear layout
+        let y = 105.0 + self.next_line as f64 * LINE_HEIGHT;
+        self.next_line += rows;
+        (self.column_x, y)
+    }
+
+    /// First row of a routine with the given marker position.
+    pub fn first_row(&self, marker: (f64, f64), group: i16) -> Row {
+        Row {
+            x: marker.0 + ROUTINE_START_X,
+            y: marker.1,
+            group,
+        }
+    }
+
+    /// Row below `row`, whose first instruction is at the given distance from the start of the routine.
+    pub fn next_row(&self, row: &Row, marker: (f64, f64), obj_pos: f64, group: i16) -> Row {
+        Row {
+            x: marker.0 + ROUTINE_START_X - obj_pos,
+            y: row.y + LINE_HEIGHT,
+            group,
+        }
+    }
+
+    /// Records that an instruction was placed at the given x-position.
+    pub fn extend(&mut self, x: f64) {
+        self.right_edge = self.right_edge.max(x);
+    }
+}
+
+/// Whether a new row may be started at the given instruction,
+/// when the current row already holds `row_instructions` instructions.
+pub fn starts_row(routine: &Routine, idx: usize, row_instructions: usize, row_len: usize) -> bool {
+    // concurrent instructions are always on the same row as the instruction before them
+    row_instructions >= row_len && !routine.instructions[idx].is_concurrent
+}
+
+/// Amount of rows a routine is split into, at most.
+fn planned_rows(routine: &Routine, row_len: usize) -> usize {
+    let mut rows = 1;
+    let mut row_instructions = 0;
+    for idx in 0..routine.instructions.len() {
+        if starts_row(routine, idx, row_instructions, row_len) {
+            rows += 1;
+            row_instructions = 0;
+        }
+        row_instructions += 1;
+    }
+    rows
+}
@@ -0,0 +1,154 @@
+//! Named lints, and the level that each of them is reported at.
+//!
+//! Every warning of the compiler belongs to a lint. Lints can be allowed, warned about or denied
+//! from the command line, and allowed for a single line or routine with a pragma comment:
+//! `; tasm: allow(unused_alias, data_race)`.
+
+use std::collections::HashMap;
+
+use crate::core::error::{TasmError, TasmErrorType};
+
+/// Name that refers to every lint at once, e.g. `--deny warnings`.
+pub const ALL_LINTS: &str = "warnings";
+/// Prefix of comments that hold lint pragmas.
+pub const PRAGMA_PREFIX: &str = "tasm:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedRoutine,
+    UnusedAlias,
+    UnusedDisplay,
+    SpawnEmptyRoutine,
+    ShadowedCounter,
+    DataRace,
+    ConcurrentChain,
+}
+
+pub const LINTS: [Lint; 7] = [
+    Lint::UnusedRoutine,
+    Lint::UnusedAlias,
+    Lint::UnusedDisplay,
+    Lint::SpawnEmptyRoutine,
+    Lint::ShadowedCounter,
+    Lint::DataRace,
+    Lint::ConcurrentChain,
+];
+
+impl Lint {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UnusedRoutine => "unused_routine",
+            Self::UnusedAlias => "unused_alias",
+            Self::UnusedDisplay => "unused_display",
+            Self::SpawnEmptyRoutine => "spawn_empty_routine",
+            Self::ShadowedCounter => "shadowed_counter",
+            Self::DataRace => "data_race",
+            Self::ConcurrentChain => "concurrent_chain",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        LINTS.into_iter().find(|l| l.name() == name)
+    }
+
+    /// Lint that emits warnings of the given type.
+    pub fn from_etype(etype: TasmErrorType) -> Option<Self> {
+        match etype {
+            TasmErrorType::UnreachableRoutine => Some(Self::UnusedRoutine),
+            TasmErrorType::UnusedAlias => Some(Self::UnusedAlias),
+            TasmErrorType::UnusedDisplay => Some(Self::UnusedDisplay),
+            TasmErrorType::EmptyRoutineSpawn => Some(Self::SpawnEmptyRoutine),
+            TasmErrorType::ShadowedCounter => Some(Self::ShadowedCounter),
+            TasmErrorType::DataRace => Some(Self::DataRace),
+            TasmErrorType::ConcurrentChain => Some(Self::ConcurrentChain),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LintLevel {
+    /// The lint is not reported
+    Allow,
+    /// The lint is reported as a warning
+    #[default]
+    Warn,
+    /// The lint is reported as an error, which fails compilation
+    Deny,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    /// Levels of single lints set from the command line
+    levels: HashMap<Lint, LintLevel>,
+    /// Level of every other lint
+    default: LintLevel,
+    /// Lints allowed on a single line by a pragma
+    line_allows: HashMap<usize, Vec<Lint>>,
+    /// Lints allowed in a whole routine by a pragma
+    routine_allows: HashMap<String, Vec<Lint>>,
+}
+
+impl LintConfig {
+    /// Sets the level of the lint with the given name.
+    /// `warnings` sets the level of every lint that is not set by name.
+    pub fn set(&mut self, name: &str, level: LintLevel) -> Result<(), String> {
+        if name == ALL_LINTS {
+            self.default = level;
+            return Ok(());
+        }
+        let lint = Lint::from_name(name).ok_or_else(|| unknown_lint(name))?;
+        self.levels.insert(lint, level);
+        Ok(())
+    }
+
+    pub fn allow_line(&mut self, line: usize, lints: &[Lint]) {
+        self.line_allows.entry(line).or_default().extend(lints);
+    }
+
+    pub fn allow_routine(&mut self, routine: &str, lints: &[Lint]) {
+        self.routine_allows
+            .entry(routine.into())
+            .or_default()
+            .extend(lints);
+    }
+
+    /// Level that the warning is reported at. Warnings that do not belong to a lint are always reported.
+    pub fn level(&self, warning: &TasmError) -> LintLevel {
+        let Some(lint) = Lint::from_etype(warning.etype) else {
+            return LintLevel::Warn;
+        };
+        let allowed = |allows: Option<&Vec<Lint>>| allows.is_some_and(|a| a.contains(&lint));
+        if allowed(self.line_allows.get(&warning.line))
+            || allowed(self.routine_allows.get(&warning.routine))
+        {
+            return LintLevel::Allow;
+        }
+        self.levels.get(&lint).copied().unwrap_or(self.default)
+    }
+}
+
+fn unknown_lint(name: &str) -> String {
+    let names = LINTS.map(Lint::name).join(", ");
+    format!("Unknown lint {name}. Expected {ALL_LINTS} or one of: {names}.")
+}
+
+/// Parses the lints of a pragma comment like `tasm: allow(unused_alias)`.
+/// Returns `None` if the comment is not a pragma.
+pub fn parse_pragma(comment: &str) -> Option<Result<Vec<Lint>, String>> {
+    let pragma = comment.trim().strip_prefix(PRAGMA_PREFIX)?.trim();
+    let Some(names) = pragma
+        .strip_prefix("allow(")
+        .and_then(|p| p.strip_suffix(')'))
+    else {
+        return Some(Err(format!(
+            "Bad pragma {pragma}. Expected allow(<lint>, ...)."
+        )));
+    };
+    Some(
+        names
+            .split(',')
+            .map(|name| Lint::from_name(name.trim()).ok_or_else(|| unknown_lint(name.trim())))
+            .collect(),
+    )
+}
@@ -1,28 +1,58 @@
 use gdlib::{
     gdlevel::Level,
-    gdobj::{GDObjConfig, ItemType, misc::text},
+    gdobj::{GDObjConfig, Item, ItemType, misc::text},
 };
 
 use crate::{
     core::{
-        consts::{ENTRY_POINT, GROUP_LIMIT, INIT_ROUTINE},
+        analysis::{Resource, concurrent_chain_issues, data_races, unreachable_routines},
+        consts::{
+            ANNOTATION_LAYER, BREAKPOINT_COUNTER, ENTRY_POINT, GROUP_LIMIT, INIT_ROUTINE,
+            TRACE_LAST_COUNTER,
+        },
         error::{TasmError, TasmErrorType},
-        structs::{HandlerArgs, HandlerData, InstrType, Instruction, Routine, Tasm, TasmValue},
+        layout::{Layout, Row, starts_row},
+        lint::LintLevel,
+        sourcemap::SourceMapEntry,
+        structs::{
+            DebugInfo, HandlerArgs, HandlerData, InstrType, Instruction, Routine, Tasm, TasmValue,
+        },
+        timing::{InstrTiming, RoutineTiming},
+    },
+    instr::{
+        fns::{debug_controls, ioblock, spawn_trg, trace_header, trace_routine},
+        get_item_spec,
     },
-    instr::{fns::ioblock, get_item_spec},
 };
 
 use alloc::borrow::Cow;
-use std::collections::HashMap;
+use core::ops::RangeInclusive;
+use std::{collections::HashMap, io::IsTerminal};
 
 pub mod consts {
     pub const ENTRY_POINT: &str = "_start";
     pub const INIT_ROUTINE: &str = "_init";
     pub const GROUP_LIMIT: i16 = 9_999;
+    /// Counter that shows the line of the last breakpoint hit in debug mode.
+    pub const BREAKPOINT_COUNTER: i16 = 8_999;
+    /// Counter that holds the group of the last routine entered when tracing.
+    /// Activation counters of each routine are placed right below it.
+    pub const TRACE_LAST_COUNTER: i16 = 8_998;
+    /// Editor layer of the source annotations, so that they can be hidden in the editor.
+    pub const ANNOTATION_LAYER: i16 = 1;
 }
+pub mod analysis;
+pub mod diagnostics;
 pub mod error;
 pub mod flags;
+pub mod layout;
+pub mod lint;
+pub mod peephole;
+pub mod scheduler;
+pub mod sourcemap;
 pub mod structs;
+pub mod suggest;
+pub mod timing;
 
 pub type HandlerReturn = Result<HandlerData, TasmError>;
 pub type HandlerFn = for<'a> fn(HandlerArgs<'a>) -> HandlerReturn;
@@ -49,6 +79,12 @@ impl Tasm {
     pub fn handle_routines(&mut self, level_name: &str) -> Result<Level, Vec<TasmError>> {
         // clear errors
         self.errors.clear();
+        self.warnings.truncate(self.parse_warnings);
+        self.source_map.clear();
+        self.spawn_groups.clear();
+        self.groups_saved = 0;
+        self.timing.clear();
+        self.reserved_items.clear();
 
         let spacing = match self.release_mode {
             true => 1.0,
@@ -59,16 +95,85 @@ impl Tasm {
         self.aliases.ptrpos_id = self.mem_end_counter;
         let mut level = Level::new(level_name, "tasm", None, None);
 
+        // counted before unreachable routines are removed, since their group IDs stay reserved
         let routine_count = self.routines.len();
         self.curr_group = routine_count as i16 + self.group_offset + 1;
 
+        // programs without an entry point are libraries, whose routines are all used elsewhere
+        if self.has_entry_point {
+            self.eliminate_dead_routines();
+        }
+        self.check_data_races();
+        self.check_concurrent_chains();
+
+        if self.release_mode {
+            for routine in &mut self.routines {
+                let removed = peephole::optimise(routine);
+                verbose_log!(
+                    self,
+                    "Optimised {} instructions out of {}",
+                    removed,
+                    routine.ident
+                );
+
+                // every instruction of the init routine runs at the start of the level anyways
+                if routine.ident != INIT_ROUTINE {
+                    let packed = scheduler::schedule(routine);
+                    verbose_log!(
+                        self,
+                        "Scheduled {} instructions of {} concurrently",
+                        packed,
+                        routine.ident
+                    );
+                }
+            }
+        }
+
+        // debug mode reserves one group for resuming every routine after a breakpoint
+        if self.debug_mode {
+            self.debug_info = Some(DebugInfo {
+                routine_groups: self
+                    .routines
+                    .iter()
+                    .filter(|r| r.ident != INIT_ROUTINE)
+                    .map(|r| r.group)
+                    .collect(),
+                continue_group: self.curr_group,
+                line_counter: BREAKPOINT_COUNTER,
+            });
+            self.curr_group += 1;
+            self.reserved_items.push((
+                BREAKPOINT_COUNTER..=BREAKPOINT_COUNTER,
+                "breakpoint indicator",
+            ));
+        }
+
+        if self.trace_mode {
+            let first = TRACE_LAST_COUNTER - routine_count as i16;
+            self.reserved_items
+                .push((first..=TRACE_LAST_COUNTER, "trace panel"));
+            for obj in trace_header() {
+                level.add_object(obj);
+            }
+        }
+        // the counters of the debug tools are fixed, so the program must not use them
+        self.check_debug_counters();
+
+        let mut layout = Layout::new(self.layout, &self.routines, routine_count);
+        let routines_height = layout.routines_height();
+        // the trace panel starts 3 rows below the IOBlock row, with a row for every routine
+        let trace_height = match self.trace_mode {
+            true => 30.0 * (routine_count + 3) as f64,
+            false => 0.0,
+        };
+
         // need to take to iteration with mutable references to self in self.push_error and self.handle_instruction
         let routines = core::mem::take(&mut self.routines);
         for routine in &routines {
             // setup position variables
             let mut obj_pos = 0.0;
-            // subtracting from group offset ensures that high group IDs are still placed close to y=0
-            let rtn_ypos = 75.0 + ((routine.group - self.group_offset) as f64) * 30.0;
+            let marker = layout.place_routine(routine, self.group_offset);
+            let mut row = layout.first_row(marker, routine.group);
             if self.curr_group > GROUP_LIMIT {
                 push_error_lineless(
                     &mut self.errors,
@@ -87,29 +192,68 @@ impl Tasm {
             if routine.ident != INIT_ROUTINE {
                 // routine marker
                 level.add_object(text(
-                    &GDObjConfig::new().pos(0.0, rtn_ypos).scale(0.6, 0.6),
+                    &GDObjConfig::new().pos(marker.0, marker.1).scale(0.6, 0.6),
                     format!("{}: {}", routine.group, routine.ident),
                     0,
                 ));
+
+                if self.trace_mode {
+                    for obj in trace_routine(routine, self.group_offset, (row.x, row.y)) {
+                        level.add_object(obj);
+                    }
+                }
+                self.timing
+                    .push(RoutineTiming::new(&routine.ident, routine.group));
             }
 
             let mut previous_spacing_amount = 0.0;
+            let mut row_start = 0.0;
+            let mut row_instructions = 0;
 
             // starting position of objects: (15, 75 + curr_group * 15)
-            for instr in routine.instructions.iter() {
+            for (idx, instr) in routine.instructions.iter().enumerate() {
+                if routine.ident != INIT_ROUTINE
+                    && let Some(row_len) = layout.row_len()
+                    && starts_row(routine, idx, row_instructions, row_len)
+                    // rows that take no time cannot be continued from the tick before they end
+                    && obj_pos > row_start
+                {
+                    let next = layout.next_row(&row, marker, obj_pos, self.curr_group);
+                    self.curr_group += 1;
+                    // spawned from the last tick of the row, so that the next row starts right after it.
+                    // it shares the control ID of the routine, so that stopping the routine stops every row
+                    level.add_object(spawn_trg(
+                        &GDObjConfig::new()
+                            .pos(row.pos_x(obj_pos - spacing), row.y)
+                            .groups([row.group])
+                            .spawnable(true)
+                            .multitrigger(true)
+                            .set_control_id(routine.group),
+                        next.group,
+                    ));
+                    row = next;
+                    row_start = obj_pos;
+                    row_instructions = 0;
+                }
+
                 self.handle_instruction(
                     instr,
                     routine,
                     &mut previous_spacing_amount,
                     &mut obj_pos,
-                    rtn_ypos,
+                    &row,
                     spacing,
-                    routine_count,
+                    routines_height,
+                    trace_height,
                     &mut level,
                 );
+                layout.extend(row.pos_x(obj_pos));
+                row_instructions += 1;
             }
         }
         self.routines = routines;
+        self.check_unused_displays();
+        self.apply_lint_levels();
 
         if self.start_rtn_group != 0 {
             let ioblock_result = ioblock(HandlerArgs {
@@ -131,6 +275,13 @@ impl Tasm {
             }
         }
 
+        if let Some(debug) = &self.debug_info {
+            // continue block and breakpoint indicator
+            for obj in debug_controls(debug) {
+                level.add_object(obj);
+            }
+        }
+
         if !self.errors.is_empty() {
             // Given that we won't be using this TASM-object again (since we faild to compile),
             // taking the errors will be ultimately more efficient.
@@ -140,15 +291,227 @@ impl Tasm {
         }
     }
 
+    /// Reserves items for a part of the program that the compiler generates, like the memory.
+    /// Returns the details of an error if another part already uses any of them.
+    fn reserve_items(
+        &mut self,
+        items: RangeInclusive<i16>,
+        user: &'static str,
+    ) -> Result<(), String> {
+        let overlap = self
+            .reserved_items
+            .iter()
+            .find(|(r, _)| r.start() <= items.end() && items.start() <= r.end());
+        if let Some((other, other_user)) = overlap {
+            return Err(format!(
+                "The {user} uses items {} to {}, which overlap items {} to {} of the {other_user}.",
+                items.start(),
+                items.end(),
+                other.start(),
+                other.end()
+            ));
+        }
+        self.reserved_items.push((items, user));
+        Ok(())
+    }
+
+    /// Errors on counters of the program that are also used by the debug tools.
+    fn check_debug_counters(&mut self) {
+        for routine in &self.routines {
+            for instr in &routine.instructions {
+                for arg in &instr.args {
+                    let TasmValue::Counter(c) = *arg else {
+                        continue;
+                    };
+                    let Some((_, user)) = self.reserved_items.iter().find(|(r, _)| r.contains(&c))
+                    else {
+                        continue;
+                    };
+                    self.errors.push(TasmError {
+                        etype: TasmErrorType::OverlappingItems,
+                        file: self.fname.clone(),
+                        routine: routine.ident.clone(),
+                        error: true,
+                        line: instr.line_number,
+                        span: None,
+                        details: format!("C{c} is also used by the {user}."),
+                        help: Some(
+                            "Use a different counter, or compile without --debug and --trace."
+                                .into(),
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Warns about routines that are never spawned, and drops them in release mode.
+    /// Their groups are not reused, so that group literals in the program stay valid.
+    fn eliminate_dead_routines(&mut self) {
+        let unreachable = unreachable_routines(&self.routines);
+        for &idx in &unreachable {
+            let routine = &self.routines[idx];
+            let details = match self.release_mode {
+                true => format!(
+                    "Routine {} is never spawned, so it is removed. Its group ID stays reserved.",
+                    routine.ident
+                ),
+                false => format!(
+                    "Routine {} is never spawned, and is removed in release mode.",
+                    routine.ident
+                ),
+            };
+            self.warnings.push(TasmError {
+                etype: TasmErrorType::UnreachableRoutine,
+                file: self.fname.clone(),
+                routine: routine.ident.clone(),
+                error: false,
+                line: routine.instructions.first().map_or(0, |i| i.line_number),
+                span: None,
+                details,
+                help: Some(format!(
+                    "Spawn {} from a reachable routine, remove it, or mark it with @export.",
+                    routine.ident
+                )),
+            });
+        }
+
+        if self.release_mode {
+            for &idx in unreachable.iter().rev() {
+                let routine = self.routines.remove(idx);
+                verbose_log!(self, "Removed unreachable routine {}", routine.ident);
+            }
+        }
+    }
+
+    /// Warns about routines that may write the same item or memory at the same time.
+    fn check_data_races(&mut self) {
+        for race in data_races(&self.routines) {
+            let (first, first_line) = race.first;
+            let (second, second_line) = race.second;
+            let (first, second) = (&self.routines[first], &self.routines[second]);
+            self.warnings.push(TasmError {
+                etype: TasmErrorType::DataRace,
+                file: self.fname.clone(),
+                routine: second.ident.clone(),
+                error: false,
+                line: second_line,
+                span: None,
+                details: format!(
+                    "{} may write {} while {} writes it on line {}, since they may run at the same time.",
+                    second.ident,
+                    race.resource,
+                    first.ident,
+                    first_line + 1
+                ),
+                help: None,
+            });
+        }
+    }
+
+    /// Warns about chains of concurrent instructions whose result depends on execution order.
+    /// Only chains written with `~` are checked, since scheduled chains are independent.
+    fn check_concurrent_chains(&mut self) {
+        for routine in &self.routines {
+            for issue in concurrent_chain_issues(&self.routines, routine) {
+                let (start, end) = issue.lines;
+                self.warnings.push(TasmError {
+                    etype: TasmErrorType::ConcurrentChain,
+                    file: self.fname.clone(),
+                    routine: routine.ident.clone(),
+                    error: false,
+                    line: start,
+                    span: None,
+                    details: format!(
+                        "Concurrent chain on lines {}-{}: {}",
+                        start + 1,
+                        end + 1,
+                        issue.details
+                    ),
+                    help: None,
+                });
+            }
+        }
+    }
+
+    /// Warns about displays of items that no instruction uses.
+    /// Items of the memory and the console are used by their instructions without naming them.
+    fn check_unused_displays(&mut self) {
+        let instructions = self.routines.iter().flat_map(|r| &r.instructions);
+        // raw objects may use any item
+        if instructions.clone().any(|i| i.ident.starts_with("RAW")) {
+            return;
+        }
+        let used = instructions
+            .clone()
+            .filter(|i| i.ident != "DISPLAY")
+            .flat_map(|i| i.args.iter().filter_map(get_item_spec))
+            .collect::<Vec<_>>();
+        let mem_range = self.mem_info.as_ref().map(|m| {
+            let end = m.ptrpos.to_counter_id().unwrap_or(m.start_counter_id);
+            m.start_counter_id..=end
+        });
+        let console_register = self
+            .console_info
+            .as_ref()
+            .and_then(|c| get_item_spec(&c.register));
+
+        for instr in instructions.filter(|i| i.ident == "DISPLAY") {
+            let Some(item) = instr.args.first().and_then(get_item_spec) else {
+                continue;
+            };
+            let id = match item {
+                Item::Counter(id) | Item::Timer(id) => id,
+                // attempts, points and the main time change on their own
+                _ => continue,
+            };
+            if used.contains(&item)
+                || console_register == Some(item)
+                || mem_range.as_ref().is_some_and(|r| r.contains(&id))
+            {
+                continue;
+            }
+            self.warnings.push(TasmError {
+                etype: TasmErrorType::UnusedDisplay,
+                file: self.fname.clone(),
+                routine: INIT_ROUTINE.into(),
+                error: false,
+                line: instr.line_number,
+                span: None,
+                details: format!(
+                    "{} is displayed, but no instruction uses it.",
+                    Resource::Item(item)
+                ),
+                help: Some("Remove the DISPLAY instruction.".into()),
+            });
+        }
+    }
+
+    /// Drops allowed warnings, and turns denied warnings into errors.
+    fn apply_lint_levels(&mut self) {
+        for mut warning in core::mem::take(&mut self.warnings) {
+            match self.lints.level(&warning) {
+                LintLevel::Allow => (),
+                LintLevel::Warn => self.warnings.push(warning),
+                LintLevel::Deny => {
+                    warning.error = true;
+                    self.errors.push(warning);
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn handle_instruction(
         &mut self,
         instr: &Instruction,
         routine: &Routine,
         previous_spacing_amount: &mut f64,
         obj_pos: &mut f64,
-        rtn_ypos: f64,
+        row: &Row,
         spacing: f64,
-        routine_count: usize,
+        routines_height: f64,
+        trace_height: f64,
         level: &mut Level,
     ) {
         let instr_args: Cow<'_, [TasmValue]> =
@@ -192,6 +555,32 @@ impl Tasm {
             }
         }
 
+        // check that the console exists before printing to it
+        if instr.itype == InstrType::Console && self.console_info.is_none() {
+            push_error(
+                &mut self.errors,
+                &self.fname,
+                TasmErrorType::NonexistentConsoleAccess,
+                instr.line_number,
+                routine.ident.clone(),
+                "Cannot print to the console when none exists.".to_string(),
+            );
+            return;
+        }
+
+        // check that the framebuffer exists before plotting to it
+        if instr.itype == InstrType::Framebuffer && self.fb_info.is_none() {
+            push_error(
+                &mut self.errors,
+                &self.fname,
+                TasmErrorType::NonexistentFramebufferAccess,
+                instr.line_number,
+                routine.ident.clone(),
+                format!("Cannot use {} when no framebuffer exists.", instr.ident),
+            );
+            return;
+        }
+
         // check that any bad assignments aren't happening
         if instr.itype == InstrType::Arithmetic {
             // first argument is always the result
@@ -226,17 +615,19 @@ impl Tasm {
             } else {
                 // in the case of a normal position-dependent instruction
                 // negate usual position to place normal triggers in init routine
-                // before the x=0 line to make the instantly execute at the level start
-                GDObjConfig::default().pos(-15.0 - *obj_pos, rtn_ypos)
+                // before the x=0 line to make the instantly execute at the level start.
+                // they are placed left of the item displays and collision blocks
+                GDObjConfig::default().pos(-195.0 - *obj_pos, row.y)
             }
         } else {
             // normal trigger placement for everything else
             GDObjConfig::default()
-                .pos(105.0 + *obj_pos, rtn_ypos)
-                .groups([routine.group])
+                .pos(row.pos_x(*obj_pos), row.y)
+                .groups([row.group])
         }
         .multitrigger(true);
 
+        let instr_pos = cfg.pos;
         let flag_assoc = instr
             .flags
             .iter()
@@ -268,13 +659,20 @@ impl Tasm {
             memreg: &self.aliases.memreg,
             ptrpos_id: self.aliases.ptrpos_id,
             displayed_items: self.displayed_items,
-            routine_count,
+            colblocks: self.colblocks,
+            inputs: self.inputs,
+            routines_height,
+            trace_height,
             mem_end_counter: self.mem_end_counter,
 
             flags: instr.flags.as_slice(),
             flag_by_ident: flag_assoc,
 
             mem_info: self.mem_info.as_ref(),
+            console_info: self.console_info.as_ref(),
+            fb_info: self.fb_info.as_ref(),
+            debug_info: self.debug_info.as_ref(),
+            spawn_groups: Some(&self.spawn_groups),
         };
 
         let data = match handler(args) {
@@ -286,12 +684,48 @@ impl Tasm {
                 return;
             }
         };
-        for obj in data.objects.into_iter() {
+        let first_index = level
+            .get_decrypted_data_ref()
+            .map_or(0, |d| d.objects.len());
+        for (i, obj) in data.objects.into_iter().enumerate() {
+            self.source_map.push(SourceMapEntry::new(
+                first_index + i,
+                &obj,
+                &routine.ident,
+                &instr.ident,
+                instr.line_number,
+            ));
             level.add_object(obj);
         }
 
+        // init instructions place their objects wherever they need to,
+        // so only position-dependent instructions are annotated
+        if self.annotate && instr.itype != InstrType::Init {
+            level.add_object(text(
+                &GDObjConfig::new()
+                    .pos(instr_pos.0, instr_pos.1 + 12.0)
+                    .scale(0.2, 0.2)
+                    .editor_layer_1(ANNOTATION_LAYER),
+                format!("{} L{}", instr.ident, instr.line_number + 1),
+                0,
+            ));
+        }
+
+        if routine.ident != INIT_ROUTINE
+            && let Some(timing) = self.timing.last_mut()
+        {
+            timing.instructions.push(InstrTiming::new(
+                instr.itype,
+                &instr.args,
+                (*obj_pos / spacing) as usize,
+                data.skip_spaces as usize,
+            ));
+        }
+
         let skip_spaces = data.skip_spaces as f64 * spacing;
         self.curr_group += data.used_extra_groups;
+        self.spawn_groups.extend(data.new_spawn_groups);
+        self.groups_saved += data.shared_groups;
         *obj_pos += skip_spaces;
         *previous_spacing_amount = skip_spaces;
 
@@ -299,6 +733,14 @@ impl Tasm {
             self.displayed_items += 1;
         }
 
+        if data.added_colblock {
+            self.colblocks += 1;
+        }
+
+        if data.added_input {
+            self.inputs += 1;
+        }
+
         // this if statement handles the logic of keeping track of the ptr group
         // it is necessary for instructions such as MRESET and MPTR which move the pointer
         // this information is only updated if it is set. this information is set
@@ -318,6 +760,12 @@ impl Tasm {
                 return;
             }
 
+            let items = m.start_counter_id..=m.ptrpos.to_counter_id().unwrap();
+            if let Err(details) = self.reserve_items(items, "memory") {
+                self.push_overlap_error(details, instr, routine);
+                return;
+            }
+
             // assigning new mem info, also assign the aliases
 
             self.mem_info = Some(m);
@@ -329,6 +777,47 @@ impl Tasm {
             // assign aliases themselves
         }
 
+        if let Some(c) = data.new_console {
+            if let Some(existing) = &self.console_info {
+                push_error(
+                    &mut self.errors,
+                    &self.fname,
+                    TasmErrorType::MultipleConsoleInstances,
+                    instr.line_number,
+                    routine.ident.clone(),
+                    format!("Console was already created on line {}.", existing.line + 1),
+                );
+                return;
+            }
+            if let Err(details) = self.reserve_items(c.items.clone(), "console") {
+                self.push_overlap_error(details, instr, routine);
+                return;
+            }
+            self.console_info = Some(c);
+        }
+
+        if let Some(fb) = data.new_fb {
+            if let Some(existing) = &self.fb_info {
+                push_error(
+                    &mut self.errors,
+                    &self.fname,
+                    TasmErrorType::MultipleFramebufferInstances,
+                    instr.line_number,
+                    routine.ident.clone(),
+                    format!(
+                        "Framebuffer was already created on line {}.",
+                        existing.line + 1
+                    ),
+                );
+                return;
+            }
+            if let Err(details) = self.reserve_items(fb.items.clone(), "framebuffer") {
+                self.push_overlap_error(details, instr, routine);
+                return;
+            }
+            self.fb_info = Some(fb);
+        }
+
         if data.ptr_group != 0 {
             self.ptr_group = data.ptr_group
         }
@@ -337,6 +826,22 @@ impl Tasm {
             self.ptr_reset_group = data.ptr_reset_group
         }
     }
+
+    fn push_overlap_error(&mut self, details: String, instr: &Instruction, routine: &Routine) {
+        self.errors.push(TasmError {
+            etype: TasmErrorType::OverlappingItems,
+            file: self.fname.clone(),
+            routine: routine.ident.clone(),
+            error: true,
+            line: instr.line_number,
+            span: None,
+            details,
+            help: Some(
+                "Move one of them with the start counter of CONSOLE or FRAMEBUFFER, the range of MALLOC or --mem-end-counter."
+                    .into(),
+            ),
+        });
+    }
 }
 
 pub fn push_error(
@@ -353,7 +858,9 @@ pub fn push_error(
         routine: rtn,
         error: true,
         line,
+        span: None,
         details,
+        help: None,
     })
 }
 
@@ -369,13 +876,28 @@ pub fn push_error_lineless(
         routine: String::new(),
         error: true,
         line: 0,
+        span: None,
         details,
+        help: None,
     })
 }
 
+/// Errors and warnings are only colored when printed to a terminal.
+fn use_color() -> bool {
+    std::io::stdout().is_terminal()
+}
+
+pub fn print_warnings(ws: &[TasmError]) {
+    let color = use_color();
+    for w in ws {
+        println!("warning: {}", w.render(color));
+    }
+}
+
 pub fn print_errors(es: Vec<TasmError>, err_msg: &str) {
+    let color = use_color();
     println!("{err_msg} with {} errors:", es.len());
     for e in es {
-        println!("{e}");
+        println!("{}", e.render(color));
     }
 }
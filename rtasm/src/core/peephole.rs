@@ -0,0 +1,243 @@
+//! Release mode optimisations over the instructions of a routine.
+//!
+//! Every optimised instruction is replaced by a 1-tick wait, so that each remaining
+//! instruction still executes on the same tick as before. Waits are then merged,
+//! which does not change timing either, since waits do not emit any objects.
+
+use crate::{
+    core::structs::{InstrType, Instruction, Routine, TasmValue},
+    instr::find_handler,
+};
+
+/// Counter or timer, with its ID. Other items are never optimised.
+#[derive(PartialEq, Clone, Copy)]
+enum Var {
+    Counter(i16),
+    Timer(i16),
+}
+
+impl Var {
+    fn from_value(v: &TasmValue) -> Option<Self> {
+        match v {
+            TasmValue::Counter(id) => Some(Self::Counter(*id)),
+            TasmValue::Timer(id) => Some(Self::Timer(*id)),
+            _ => None,
+        }
+    }
+
+    /// Counters round every result, so only integers may be folded into them.
+    fn can_fold(self, values: &[f64]) -> bool {
+        matches!(self, Self::Timer(_)) || values.iter().all(|v| v.fract() == 0.0)
+    }
+}
+
+/// Arithmetic on a single item with a number literal, like `ADD C1, 2`.
+struct ConstOp {
+    ident: &'static str,
+    var: Var,
+    value: f64,
+}
+
+impl ConstOp {
+    fn from_instr(instr: &Instruction) -> Option<Self> {
+        let [target, TasmValue::Number(value)] = instr.args.as_slice() else {
+            return None;
+        };
+        let ident = match instr.ident.as_str() {
+            "MOV" => "MOV",
+            "ADD" => "ADD",
+            "SUB" => "SUB",
+            "MUL" => "MUL",
+            "DIV" => "DIV",
+            _ => return None,
+        };
+        Some(Self {
+            ident,
+            var: Var::from_value(target)?,
+            value: *value,
+        })
+    }
+
+    fn is_noop(&self) -> bool {
+        match self.ident {
+            "ADD" | "SUB" => self.value == 0.0,
+            "MUL" | "DIV" => self.value == 1.0,
+            _ => false,
+        }
+    }
+
+    /// Value to add, if this is an addition or subtraction.
+    fn addend(&self) -> Option<f64> {
+        match self.ident {
+            "ADD" => Some(self.value),
+            "SUB" => Some(-self.value),
+            _ => None,
+        }
+    }
+}
+
+/// Whether or not the instruction may be optimised at all.
+fn is_plain(instr: &Instruction) -> bool {
+    !instr.is_concurrent && instr.flags.is_empty()
+}
+
+fn wait(line_number: usize, ticks: i32) -> Instruction {
+    rebuild(
+        "WAIT",
+        vec![TasmValue::Number(ticks as f64)],
+        InstrType::Wait,
+        line_number,
+    )
+}
+
+fn rebuild(ident: &str, args: Vec<TasmValue>, itype: InstrType, line_number: usize) -> Instruction {
+    Instruction {
+        handler_fn: find_handler(ident, &args).unwrap(),
+        ident: ident.into(),
+        itype,
+        line_number,
+        args,
+        flags: vec![],
+        is_concurrent: false,
+    }
+}
+
+/// Folds `next` into `prev`, if both are constant operations on the same item.
+/// Returns the replacement for `prev`, which is `None` if the two cancel each other out.
+fn fold(prev: &Instruction, next: &Instruction) -> Option<Option<Instruction>> {
+    let a = ConstOp::from_instr(prev)?;
+    let b = ConstOp::from_instr(next)?;
+    if a.var != b.var || !a.var.can_fold(&[a.value, b.value]) {
+        return None;
+    }
+
+    let target = prev.args[0].clone();
+    let arith = |ident: &str, value: f64| {
+        Some(rebuild(
+            ident,
+            vec![target.clone(), TasmValue::Number(value)],
+            InstrType::Arithmetic,
+            prev.line_number,
+        ))
+    };
+
+    match (a.ident, b.ident) {
+        ("MOV", _) => {
+            let value = match b.ident {
+                "MUL" => a.value * b.value,
+                _ => a.value + b.addend()?,
+            };
+            Some(arith("MOV", value))
+        }
+        ("MUL", "MUL") => match a.value * b.value {
+            1.0 => Some(None),
+            value => Some(arith("MUL", value)),
+        },
+        _ => {
+            let sum = a.addend()? + b.addend()?;
+            match sum {
+                0.0 => Some(None),
+                s if s < 0.0 => Some(arith("SUB", -s)),
+                s => Some(arith("ADD", s)),
+            }
+        }
+    }
+}
+
+/// Whether or not `next` overwrites the result of `prev` without reading it.
+fn overwrites(prev: &Instruction, next: &Instruction) -> bool {
+    if prev.itype != InstrType::Arithmetic || next.ident != "MOV" {
+        return false;
+    }
+    let Some(target) = Var::from_value(&prev.args[0]) else {
+        return false;
+    };
+    Var::from_value(&next.args[0]) == Some(target)
+        && next.args[1..].iter().all(|a| {
+            matches!(a, TasmValue::Number(_)) || Var::from_value(a).is_some_and(|v| v != target)
+        })
+}
+
+/// Merges runs of `NOP` and `WAIT` into a single `WAIT`.
+fn merge_waits(instructions: Vec<Instruction>) -> Vec<Instruction> {
+    let ticks = |instr: &Instruction| match instr.ident.as_str() {
+        "NOP" => Some(1),
+        "WAIT" => instr.args[0].to_int(),
+        _ => None,
+    };
+
+    let mut merged: Vec<Instruction> = Vec::with_capacity(instructions.len());
+    let mut run: Option<(usize, i32)> = None; // (line, ticks)
+    let mut iter = instructions.into_iter().peekable();
+    while let Some(instr) = iter.next() {
+        // a concurrent instruction moves back by the length of the previous one
+        let next_is_concurrent = iter.peek().is_some_and(|n| n.is_concurrent);
+        match ticks(&instr) {
+            Some(t) if is_plain(&instr) && !next_is_concurrent => {
+                let (line, total) = run.get_or_insert((instr.line_number, 0));
+                *line = (*line).min(instr.line_number);
+                *total += t;
+            }
+            _ => {
+                if let Some((line, total)) = run.take() {
+                    merged.push(wait(line, total));
+                }
+                merged.push(instr);
+            }
+        }
+    }
+    if let Some((line, total)) = run {
+        merged.push(wait(line, total));
+    }
+    merged
+}
+
+/// Optimises the routine in place. Returns how many instructions were removed.
+pub fn optimise(routine: &mut Routine) -> usize {
+    let before = routine.instructions.len();
+    let instructions = core::mem::take(&mut routine.instructions);
+
+    let mut out: Vec<Instruction> = Vec::with_capacity(before);
+    // last instruction that the next one may be folded into.
+    // only waits inserted by this pass may come after it
+    let mut target: Option<usize> = None;
+    for instr in instructions {
+        if !is_plain(&instr) {
+            target = None;
+            out.push(instr);
+            continue;
+        }
+
+        if ConstOp::from_instr(&instr).is_some_and(|op| op.is_noop()) {
+            out.push(wait(instr.line_number, 1));
+            continue;
+        }
+
+        if let Some(t) = target {
+            if let Some(folded) = fold(&out[t], &instr) {
+                match folded {
+                    Some(folded) => out[t] = folded,
+                    None => {
+                        out[t] = wait(out[t].line_number, 1);
+                        target = None;
+                    }
+                }
+                out.push(wait(instr.line_number, 1));
+                continue;
+            }
+
+            if overwrites(&out[t], &instr) {
+                out[t] = wait(out[t].line_number, 1);
+            }
+        }
+
+        target = match instr.itype {
+            InstrType::Arithmetic => Some(out.len()),
+            _ => None,
+        };
+        out.push(instr);
+    }
+
+    routine.instructions = merge_waits(out);
+    before - routine.instructions.len()
+}
@@ -0,0 +1,84 @@
+//! Release mode scheduling of independent instructions onto the same tick.
+//!
+//! Instructions on the same tick may execute in any order, so an instruction is only
+//! moved onto the tick of the previous one if it does not depend on any of the items that are
+//! used on that tick, and no instruction on that tick depends on it.
+
+use gdlib::gdobj::Item;
+
+use crate::{
+    core::structs::{InstrType, Instruction, Routine, TasmValue},
+    instr::get_item_spec,
+};
+
+/// Items read and written by an instruction.
+struct Access {
+    reads: Vec<Item>,
+    writes: Vec<Item>,
+}
+
+impl Access {
+    /// Returns `None` if the instruction may not be scheduled.
+    fn from_instr(instr: &Instruction) -> Option<Self> {
+        // only arithmetic takes exactly one tick, and only uses items
+        if instr.itype != InstrType::Arithmetic {
+            return None;
+        }
+        // the items of aliases are only known when compiling
+        if instr.args.iter().any(|a| matches!(a, TasmValue::Alias(_))) {
+            return None;
+        }
+
+        let target = get_item_spec(&instr.args[0])?;
+        let mut reads = instr.args[1..]
+            .iter()
+            .filter_map(get_item_spec)
+            .collect::<Vec<_>>();
+        // every operation except a move depends on the previous value of the target
+        if instr.ident != "MOV" {
+            reads.push(target);
+        }
+        Some(Self {
+            reads,
+            writes: vec![target],
+        })
+    }
+
+    fn conflicts(&self, other: &Self) -> bool {
+        self.writes
+            .iter()
+            .any(|w| other.reads.contains(w) || other.writes.contains(w))
+            || other.writes.iter().any(|w| self.reads.contains(w))
+    }
+}
+
+/// Packs independent instructions into the same tick by making them concurrent.
+/// Returns how many instructions were packed.
+pub fn schedule(routine: &mut Routine) -> usize {
+    let instructions = &mut routine.instructions;
+    let mut packed = 0;
+    // accesses of every instruction on the current tick
+    let mut tick: Vec<Access> = vec![];
+    for i in 0..instructions.len() {
+        // a concurrent instruction moves back onto the tick of the one before it,
+        // so ticks written with `~` are left as they are
+        let next_is_concurrent = instructions.get(i + 1).is_some_and(|n| n.is_concurrent);
+        let access = match instructions[i].is_concurrent || next_is_concurrent {
+            true => None,
+            false => Access::from_instr(&instructions[i]),
+        };
+        let Some(access) = access else {
+            tick.clear();
+            continue;
+        };
+
+        if !tick.is_empty() && !tick.iter().any(|a| a.conflicts(&access)) {
+            instructions[i].is_concurrent = true;
+            packed += 1;
+        } else {
+            tick.clear();
+        }
+        tick.push(access);
+    }
+    packed
+}
@@ -0,0 +1,84 @@
+use core::fmt::Write;
+
+use gdlib::gdobj::GDObject;
+
+/// Location in the source of an object emitted by an instruction.
+#[derive(Debug, Clone)]
+pub struct SourceMapEntry {
+    /// Index of the object in the level
+    pub index: usize,
+    pub x: f64,
+    pub y: f64,
+    pub groups: Vec<i16>,
+    pub routine: String,
+    /// Mnemonic of the instruction
+    pub instruction: String,
+    pub line: usize,
+}
+
+impl SourceMapEntry {
+    pub fn new(
+        index: usize,
+        obj: &GDObject,
+        routine: &str,
+        instruction: &str,
+        line: usize,
+    ) -> Self {
+        Self {
+            index,
+            x: obj.config.pos.0,
+            y: obj.config.pos.1,
+            groups: obj.config.groups.iter().map(|g| g.id()).collect(),
+            routine: routine.into(),
+            instruction: instruction.into(),
+            line,
+        }
+    }
+}
+
+/// Escapes a string so that it can be put between quotes in JSON.
+pub fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Serialises the source map of a file to JSON.
+/// Lines are written as they appear in an editor, i.e. starting from 1.
+pub fn source_map_json(file: &str, entries: &[SourceMapEntry]) -> String {
+    let objects = entries
+        .iter()
+        .map(|e| {
+            format!(
+                r#"    {{"index": {}, "x": {}, "y": {}, "groups": [{}], "file": "{}", "routine": "{}", "instruction": "{}", "line": {}}}"#,
+                e.index,
+                e.x,
+                e.y,
+                e.groups
+                    .iter()
+                    .map(|g| g.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                escape_json(file),
+                escape_json(&e.routine),
+                escape_json(&e.instruction),
+                e.line + 1
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    format!("{{\n  \"objects\": [\n{objects}\n  ]\n}}\n")
+}
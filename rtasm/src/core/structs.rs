@@ -1,5 +1,6 @@
 use alloc::borrow::Cow;
-use std::{collections::HashMap, hint::unreachable_unchecked};
+use core::{fmt::Display, hint::unreachable_unchecked, ops::RangeInclusive};
+use std::collections::HashMap;
 
 use gdlib::gdobj::{GDObjConfig, GDObject, Item};
 
@@ -8,18 +9,34 @@ use crate::core::{
     consts::GROUP_LIMIT,
     error::{ParseErrorType, TasmError},
     flags::Flag,
+    layout::LayoutMode,
+    lint::LintConfig,
+    sourcemap::SourceMapEntry,
+    timing::RoutineTiming,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
 pub enum InstrType {
-    Arithmetic, // any instruction that performs mathematical operations with counters
-    Init,       // any instruction that can only go into the _init routine.
-    Memory,     // any instruction that requires/interfaces with memory
-    Timer,      // any instruction that interacts with timers non-arithmetically.
-    Special,    // instructions that are miscellaneous (dont fit into any of these categories)
-    Process,    // any instruction that modifies the process flow (PAUSE, RESUME, STOP)
-    Wait,       // any instruction that waits (NOP, WAIT)
+    Arithmetic,  // any instruction that performs mathematical operations with counters
+    Init,        // any instruction that can only go into the _init routine.
+    Memory,      // any instruction that requires/interfaces with memory
+    Timer,       // any instruction that interacts with timers non-arithmetically.
+    Special,     // instructions that are miscellaneous (dont fit into any of these categories)
+    Process,     // any instruction that modifies the process flow (PAUSE, RESUME, STOP)
+    Wait,        // any instruction that waits (NOP, WAIT)
     Debug, // any instruction that is only used by the emulator, and ignored when parsing to GD objects.
+    Event, // any instruction that listens for something to happen in-level and spawns a routine when it does.
+    Console, // any instruction that requires/interfaces with the console
+    Framebuffer, // any instruction that requires/interfaces with the framebuffer
+    Spawn { kind: SpawnKind, delay: usize }, // any instruction that spawns the routines it is given, `delay` ticks after it runs
+}
+
+/// How many of the routines it is given a spawn instruction spawns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
+pub enum SpawnKind {
+    Always, // every routine
+    Maybe,  // every routine, or none of them
+    OneOf,  // exactly one of the routines
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +47,8 @@ pub enum TasmValue {
     Number(f64),
     Group(i16),
     Alias(BuiltinAlias), // use ident instead of alias type
+    /// Key-value pairs, written like `{a:b, c:d}`
+    Dict(Vec<(i16, i16)>),
     /// Default
     String(String),
 }
@@ -111,9 +130,46 @@ pub enum TasmPrimitive {
     Number, // also a float.
     Int,    // subset of number
     Group,
+    Dict,
     String,
 }
 
+impl Display for TasmPrimitive {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            Self::Item => "item",
+            Self::Timer => "timer",
+            Self::Number => "number",
+            Self::Int => "int",
+            Self::Group => "group",
+            Self::Dict => "dict",
+            Self::String => "string",
+        };
+        write!(f, "<{name}>")
+    }
+}
+
+impl Display for TasmValueType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Primitive(p) => write!(f, "{p}"),
+            Self::List(p) => write!(f, "{p}, {p}, ..."),
+        }
+    }
+}
+
+/// Formats an argset signature like it is written in a program, e.g. `<item>, <number>`.
+pub fn signature_str(sig: &[TasmValueType]) -> String {
+    match sig.is_empty() {
+        true => "no arguments".into(),
+        false => sig
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
 pub fn is_builtin_alias(s: &str) -> bool {
     matches!(
         s,
@@ -208,6 +264,7 @@ impl TasmValue {
             Self::Counter(_) | Self::Timer(_) | Self::GDItem(_) => TasmPrimitive::Item,
             Self::Number(_) => TasmPrimitive::Number,
             Self::Group(_) => TasmPrimitive::Group,
+            Self::Dict(_) => TasmPrimitive::Dict,
             Self::String(_) => TasmPrimitive::String,
             Self::Alias(a) => a.get_type(),
         }
@@ -264,6 +321,13 @@ impl TasmValue {
         }
     }
 
+    pub fn to_dict(&self) -> Option<&Vec<(i16, i16)>> {
+        match self {
+            Self::Dict(d) => Some(d),
+            _ => None,
+        }
+    }
+
     pub fn to_string(&self) -> Option<String> {
         match self {
             Self::String(s) => Some(s.to_owned()),
@@ -272,19 +336,37 @@ impl TasmValue {
     }
 }
 
-pub fn fits_arg_signature(args: &[TasmValue], sig: &[TasmValueType]) -> bool {
-    // helper fn
-    fn check_primitive(p: &TasmPrimitive, arg: &TasmValue) -> bool {
-        // check if an int is required here
-        // get_type returns `Number` for a `Number` even if it is an `Int`
-        match p {
-            TasmPrimitive::Int => arg.is_int(),
-            TasmPrimitive::Timer => arg.is_timer(),
-            // TasmPrimitive::String => true, // everything can be a string
-            // ^ can't use this because TasmValue::to_string doesn't support it
-            _ => &arg.get_type() == p,
-        }
+fn check_primitive(p: &TasmPrimitive, arg: &TasmValue) -> bool {
+    // check if an int is required here
+    // get_type returns `Number` for a `Number` even if it is an `Int`
+    match p {
+        TasmPrimitive::Int => arg.is_int(),
+        TasmPrimitive::Timer => arg.is_timer(),
+        // TasmPrimitive::String => true, // everything can be a string
+        // ^ can't use this because TasmValue::to_string doesn't support it
+        _ => &arg.get_type() == p,
     }
+}
+
+/// Index of the first argument that does not fit its position in any of the signatures.
+/// `None` if every argument fits some signature, e.g. if there are too few arguments.
+pub fn mismatched_arg<'a, I>(args: &[TasmValue], sigs: I) -> Option<usize>
+where
+    I: IntoIterator<Item = &'a [TasmValueType]> + Clone,
+{
+    args.iter().enumerate().position(|(idx, arg)| {
+        !sigs.clone().into_iter().any(|sig| match sig {
+            [TasmValueType::List(l_type)] => check_primitive(l_type, arg),
+            _ => match sig.get(idx) {
+                Some(TasmValueType::Primitive(p)) => check_primitive(p, arg),
+                Some(TasmValueType::List(_)) => true,
+                None => false,
+            },
+        })
+    })
+}
+
+pub fn fits_arg_signature(args: &[TasmValue], sig: &[TasmValueType]) -> bool {
     match sig.len() {
         0 => args.is_empty(),
         1 => match &sig[0] {
@@ -336,9 +418,20 @@ pub struct HandlerArgs<'a> {
     pub memreg: &'a TasmValue,
     pub ptrpos_id: i16,
     pub displayed_items: usize,
+    pub colblocks: usize,
+    pub inputs: usize,
     pub mem_end_counter: i16,
-    pub routine_count: usize,
+    /// Height of the area taken up by routines, which memory is placed above
+    pub routines_height: f64,
+    /// Distance from the IOBlock row to the last row of the trace panel, which keypads are placed
+    /// below. 0 if tracing is off
+    pub trace_height: f64,
     pub mem_info: Option<&'a MemInfo>,
+    pub console_info: Option<&'a ConsoleInfo>,
+    pub fb_info: Option<&'a FramebufferInfo>,
+    pub debug_info: Option<&'a DebugInfo>,
+    /// Auxiliary groups that spawn a routine, by routine group
+    pub spawn_groups: Option<&'a HashMap<i16, i16>>,
 
     pub flags: &'a [Flag],
     pub flag_by_ident: HashMap<String, &'a Flag>,
@@ -358,7 +451,17 @@ pub struct HandlerData {
     pub ptr_reset_group: i16,
     // set in display instr handler to tell the tasm object to bump displays counter
     pub added_item_display: bool,
+    // set in colblock instr handler to tell the tasm object to bump collision blocks counter
+    pub added_colblock: bool,
+    // set in input instr handler to tell the tasm object to bump keypads counter
+    pub added_input: bool,
     pub new_mem: Option<MemInfo>,
+    pub new_console: Option<ConsoleInfo>,
+    pub new_fb: Option<FramebufferInfo>,
+    // auxiliary groups created to spawn a routine, as (routine group, aux group)
+    pub new_spawn_groups: Vec<(i16, i16)>,
+    // amount of auxiliary groups that were shared instead of created
+    pub shared_groups: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -373,18 +476,64 @@ pub struct MemInfo {
     pub line: usize, // where is was created
 }
 
+#[derive(Debug, Clone)]
+pub struct ConsoleInfo {
+    /// Item that holds the value being printed
+    pub register: TasmValue,
+    /// Writes the register to the cell at the cursor
+    pub write_group: i16,
+    /// Moves the cursor to the start of the next row
+    pub newline_group: i16,
+    /// Hides all cells and moves the cursor back to the first cell
+    pub clear_group: i16,
+    /// Every item of the console, from the first cell to the register
+    pub items: RangeInclusive<i16>,
+    pub line: usize, // where it was created
+}
+
+#[derive(Debug, Clone)]
+pub struct FramebufferInfo {
+    /// Counters that hold the coordinates of the pixel to plot
+    pub x_reg: i16,
+    pub y_reg: i16,
+    /// Toggles off every pixel's triggers that do not match the coordinates
+    pub controller_group: i16,
+    /// Spawns the triggers of the matching pixel
+    pub shared_group: i16,
+    /// Triggers that turn the pixel on/off
+    pub on_group: i16,
+    pub off_group: i16,
+    /// Contains every pixel
+    pub pixels_group: i16,
+    /// Every counter of the framebuffer, from the x register to the last temporary counter
+    pub items: RangeInclusive<i16>,
+    pub line: usize, // where it was created
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugInfo {
+    /// Groups of every routine, paused when a breakpoint is hit. Other groups are not paused
+    pub routine_groups: Vec<i16>,
+    /// Resumes every routine group
+    pub continue_group: i16,
+    /// Holds the line of the last breakpoint that was hit
+    pub line_counter: i16,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct RoutineData {
     pub line_idx: usize,             // legacy: 0
     pub routine_ident: String,       // legacy: 1
     pub group_id: i16,               // legacy: 2
     pub lines: Vec<(usize, String)>, // legacy: 3
+    pub exported: bool,
 }
 
 #[derive(Debug, Default)]
 pub struct Tasm {
     pub routines: Vec<Routine>,
     pub errors: Vec<TasmError>,
+    pub warnings: Vec<TasmError>,
     /// (line_idx, routine_ident, group_id, lines as (line_idx, line_content))
     pub routine_data: Vec<RoutineData>,
     pub routine_group_map: HashMap<String, i16>, // routine ident => group id
@@ -396,12 +545,35 @@ pub struct Tasm {
     pub ptr_group: i16,
     pub ptr_reset_group: i16,
     pub displayed_items: usize,
+    pub colblocks: usize,
+    pub inputs: usize,
     pub start_rtn_group: i16,
     pub mem_info: Option<MemInfo>,
+    pub console_info: Option<ConsoleInfo>,
+    pub fb_info: Option<FramebufferInfo>,
+    pub debug_info: Option<DebugInfo>,
+    /// Auxiliary groups that spawn a routine, shared between all instructions that spawn it
+    pub spawn_groups: HashMap<i16, i16>,
+    /// Item IDs used by the compiler, like the memory or the console, along with their user
+    pub reserved_items: Vec<(RangeInclusive<i16>, &'static str)>,
+    pub groups_saved: usize,
+    /// Timing of every compiled routine, except for the init routine
+    pub timing: Vec<RoutineTiming>,
     // aliases get resolved through the map:
     pub aliases: Aliases,
     pub logs_enabled: bool,
     pub release_mode: bool,
+    pub debug_mode: bool,
+    pub trace_mode: bool,
+    pub annotate: bool,
+    pub layout: LayoutMode,
+    pub lints: LintConfig,
+    /// Warnings emitted while parsing, which are kept when compiling
+    pub parse_warnings: usize,
+    /// Routines without instructions, by name. Their group is reused by the next routine
+    pub empty_routines: HashMap<String, usize>,
+    /// Where each object emitted by an instruction came from
+    pub source_map: Vec<SourceMapEntry>,
     pub defined_aliases: HashMap<String, String>, // alias => value
     pub fname: String,
 }
@@ -427,6 +599,8 @@ pub struct Routine {
     pub ident: String,
     pub group: i16,
     pub instructions: Vec<Instruction>,
+    /// Kept even if nothing in the file spawns it
+    pub exported: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -446,6 +620,7 @@ impl Routine {
             ident: String::new(),
             group: 0,
             instructions: vec![],
+            exported: false,
         }
     }
 
@@ -459,6 +634,11 @@ impl Routine {
         self
     }
 
+    pub fn exported(mut self, exported: bool) -> Self {
+        self.exported = exported;
+        self
+    }
+
     pub fn add_instruction(&mut self, instr: Instruction) {
         self.instructions.push(instr);
     }
@@ -497,6 +677,18 @@ impl HandlerData {
         self.added_item_display = true;
         self
     }
+
+    #[inline(always)]
+    pub fn added_colblock(mut self) -> Self {
+        self.added_colblock = true;
+        self
+    }
+
+    #[inline(always)]
+    pub fn added_input(mut self) -> Self {
+        self.added_input = true;
+        self
+    }
 }
 impl Default for HandlerData {
     #[inline]
@@ -508,7 +700,13 @@ impl Default for HandlerData {
             ptr_group: 0,
             ptr_reset_group: 0,
             added_item_display: false,
+            added_colblock: false,
+            added_input: false,
             new_mem: None,
+            new_console: None,
+            new_fb: None,
+            new_spawn_groups: vec![],
+            shared_groups: 0,
         }
     }
 }
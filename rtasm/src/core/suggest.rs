@@ -0,0 +1,50 @@
+//! "Did you mean" suggestions for misspelled names.
+
+/// Amount of single-character insertions, deletions, substitutions and swaps of adjacent
+/// characters that turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // distances between prefixes of `a` and every prefix of `b`, for the last three prefixes of `a`
+    let mut before_prev = vec![0; b.len() + 1];
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let substitution = prev[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            row[j] = substitution.min(prev[j] + 1).min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before_prev[j - 2] + 1);
+            }
+        }
+        before_prev = core::mem::replace(&mut prev, row);
+    }
+    prev[b.len()]
+}
+
+/// Candidate that is closest to `name`, ignoring case.
+/// Only candidates that differ by at most a third of the length of `name` are considered,
+/// and empty candidates are never suggested.
+pub fn closest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let lowercase = name.to_lowercase();
+    let max_distance = name.chars().count().max(3) / 3;
+    candidates
+        .into_iter()
+        .filter(|&c| !c.is_empty() && c != name)
+        .map(|c| (edit_distance(&lowercase, &c.to_lowercase()), c))
+        .filter(|&(distance, _)| distance <= max_distance)
+        // ties are broken alphabetically, so suggestions do not depend on hash map order
+        .min()
+        .map(|(_, c)| c)
+}
+
+/// Suggestion for the candidate that is closest to `name`, if any is close enough.
+pub fn did_you_mean<'a, I>(name: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    closest(name, candidates).map(|c| format!("Did you mean {c}?"))
+}
@@ -0,0 +1,278 @@
+//! Static timing analysis of compiled routines.
+//!
+//! Timings are measured in ticks from the moment a routine is spawned, and are taken from
+//! the layout of the compiled instructions, so concurrency and release mode are accounted for.
+
+use core::fmt::Write;
+use std::collections::HashMap;
+
+use crate::core::{
+    sourcemap::escape_json,
+    structs::{InstrType, SpawnKind, TasmValue},
+};
+
+/// How an instruction spawns other routines.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Spawns {
+    None,
+    Always(Vec<i16>),
+    Maybe(Vec<i16>),
+    OneOf(Vec<i16>),
+}
+
+impl Spawns {
+    pub fn new(itype: InstrType, args: &[TasmValue]) -> Self {
+        let InstrType::Spawn { kind, .. } = itype else {
+            return Self::None;
+        };
+        let groups = args
+            .iter()
+            .flat_map(|a| match a {
+                TasmValue::Group(g) => vec![*g],
+                // weights or activation counts of routines
+                TasmValue::Dict(d) => d.iter().map(|(g, _)| *g).collect(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+        if groups.is_empty() {
+            return Self::None;
+        }
+        match kind {
+            SpawnKind::Always => Self::Always(groups),
+            SpawnKind::Maybe => Self::Maybe(groups),
+            SpawnKind::OneOf => Self::OneOf(groups),
+        }
+    }
+
+    /// Every routine that may be spawned.
+    pub fn targets(&self) -> &[i16] {
+        match self {
+            Self::None => &[],
+            Self::Always(t) | Self::Maybe(t) | Self::OneOf(t) => t,
+        }
+    }
+}
+
+/// Timing of a single compiled instruction.
+#[derive(Debug, Clone)]
+pub struct InstrTiming {
+    /// Tick on which the instruction is executed
+    pub tick: usize,
+    /// Ticks until the next instruction is executed
+    pub ticks: usize,
+    pub memory: bool,
+    pub spawns: Spawns,
+    /// Ticks between executing the instruction and the spawned routine starting
+    pub spawn_delay: usize,
+}
+
+impl InstrTiming {
+    pub fn new(itype: InstrType, args: &[TasmValue], tick: usize, ticks: usize) -> Self {
+        let spawn_delay = match itype {
+            InstrType::Spawn { delay, .. } => delay,
+            _ => 0,
+        };
+        Self {
+            tick,
+            ticks,
+            memory: itype == InstrType::Memory,
+            spawns: Spawns::new(itype, args),
+            spawn_delay,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoutineTiming {
+    pub ident: String,
+    pub group: i16,
+    pub instructions: Vec<InstrTiming>,
+}
+
+impl RoutineTiming {
+    pub fn new(ident: &str, group: i16) -> Self {
+        Self {
+            ident: ident.into(),
+            group,
+            instructions: vec![],
+        }
+    }
+
+    /// Ticks until the last instruction of the routine is done.
+    pub fn length(&self) -> usize {
+        self.instructions
+            .iter()
+            .map(|i| i.tick + i.ticks)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Ticks spent on memory instructions.
+    pub fn memory_ticks(&self) -> usize {
+        self.instructions
+            .iter()
+            .filter(|i| i.memory)
+            .map(|i| i.ticks)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimingReport {
+    pub routine: String,
+    pub group: i16,
+    pub length: usize,
+    pub memory_ticks: usize,
+    /// Ticks until the routine and every routine it must spawn are done.
+    /// `None` if it recurses without end.
+    pub best: Option<usize>,
+    /// Ticks until the routine and every routine it may spawn are done.
+    /// `None` if it may recurse.
+    pub worst: Option<usize>,
+}
+
+enum Visit {
+    InProgress,
+    Done(Option<usize>),
+}
+
+struct Paths<'a> {
+    by_group: HashMap<i16, &'a RoutineTiming>,
+    best: HashMap<i16, Visit>,
+    worst: HashMap<i16, Visit>,
+    /// Whether the current path ran into a routine that was still being visited.
+    /// Such paths depend on where they were entered from, so they are not memoised.
+    hit_cycle: bool,
+}
+
+impl Paths<'_> {
+    /// Length of the path through the routine on `group`, with `worst` picking the longest
+    /// possible path, and the shortest otherwise. Groups that are not routines take no time.
+    fn path(&mut self, group: i16, worst: bool) -> Option<usize> {
+        let Some(&routine) = self.by_group.get(&group) else {
+            return Some(0);
+        };
+        let memo = match worst {
+            true => &mut self.worst,
+            false => &mut self.best,
+        };
+        match memo.get(&group) {
+            // spawning a routine that is still running makes the path endless
+            Some(Visit::InProgress) => {
+                self.hit_cycle = true;
+                return None;
+            }
+            Some(Visit::Done(ticks)) => return *ticks,
+            None => memo.insert(group, Visit::InProgress),
+        };
+        let outer_hit_cycle = core::mem::replace(&mut self.hit_cycle, false);
+
+        let mut end = Some(routine.length());
+        for instr in &routine.instructions {
+            match (&instr.spawns, worst) {
+                (Spawns::None, _) | (Spawns::Maybe(_), false) => continue,
+                _ => (),
+            }
+            let mut paths = instr.spawns.targets().iter().map(|&t| self.path(t, worst));
+            let spawned = match (&instr.spawns, worst) {
+                (Spawns::OneOf(_), false) => paths.flatten().min(),
+                // any of them may be the endless one
+                _ => paths.try_fold(0, |max, p| Some(max.max(p?))),
+            };
+            end = match (end, spawned) {
+                (Some(e), Some(s)) => Some(e.max(instr.tick + instr.spawn_delay + s)),
+                _ => None,
+            };
+        }
+
+        let memo = match worst {
+            true => &mut self.worst,
+            false => &mut self.best,
+        };
+        match self.hit_cycle {
+            true => memo.remove(&group),
+            false => memo.insert(group, Visit::Done(end)),
+        };
+        self.hit_cycle |= outer_hit_cycle;
+        end
+    }
+}
+
+/// Computes the timing report of every routine.
+pub fn analyse(routines: &[RoutineTiming]) -> Vec<TimingReport> {
+    let mut paths = Paths {
+        by_group: routines.iter().map(|r| (r.group, r)).collect(),
+        best: HashMap::new(),
+        worst: HashMap::new(),
+        hit_cycle: false,
+    };
+
+    routines
+        .iter()
+        .map(|r| TimingReport {
+            routine: r.ident.clone(),
+            group: r.group,
+            length: r.length(),
+            memory_ticks: r.memory_ticks(),
+            best: paths.path(r.group, false),
+            worst: paths.path(r.group, true),
+        })
+        .collect()
+}
+
+fn ticks_str(ticks: Option<usize>) -> String {
+    ticks.map_or("unbounded".into(), |t| t.to_string())
+}
+
+/// Formats the timing reports as a table.
+pub fn timing_table(reports: &[TimingReport]) -> String {
+    let width = reports
+        .iter()
+        .map(|r| r.routine.len())
+        .chain(["routine".len()])
+        .max()
+        .unwrap();
+
+    let mut table = format!(
+        "{:<width$}  {:>5}  {:>5}  {:>6}  {:>9}  {:>9}\n",
+        "routine", "group", "ticks", "memory", "best", "worst"
+    );
+    for r in reports {
+        let _ = writeln!(
+            table,
+            "{:<width$}  {:>5}  {:>5}  {:>6}  {:>9}  {:>9}",
+            r.routine,
+            r.group,
+            r.length,
+            r.memory_ticks,
+            ticks_str(r.best),
+            ticks_str(r.worst)
+        );
+    }
+    table
+}
+
+/// Serialises the timing reports to JSON. Endless paths are written as `null`.
+pub fn timing_json(file: &str, reports: &[TimingReport]) -> String {
+    let json_ticks = |t: Option<usize>| t.map_or("null".into(), |t| t.to_string());
+    let routines = reports
+        .iter()
+        .map(|r| {
+            format!(
+                r#"    {{"routine": "{}", "group": {}, "ticks": {}, "memory_ticks": {}, "best": {}, "worst": {}}}"#,
+                escape_json(&r.routine),
+                r.group,
+                r.length,
+                r.memory_ticks,
+                json_ticks(r.best),
+                json_ticks(r.worst)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    format!(
+        "{{\n  \"file\": \"{}\",\n  \"routines\": [\n{routines}\n  ]\n}}\n",
+        escape_json(file)
+    )
+}
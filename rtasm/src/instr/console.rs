@@ -0,0 +1,325 @@
+use gdlib::gdobj::{
+    GDObjConfig, GDObject, Item,
+    misc::text,
+    triggers::{
+        CompareOp, CompareOperand, ItemAlign, Op, RoundMode, SignMode, counter_object,
+        item_compare, item_edit, toggle_trigger,
+    },
+};
+
+use crate::{
+    core::{
+        HandlerReturn,
+        consts::GROUP_LIMIT,
+        error::{TasmError, TasmErrorType},
+        flags::FlagValue,
+        structs::{ConsoleInfo, HandlerArgs, HandlerData, TasmValue},
+    },
+    instr::{fns::spawn_trg, get_flag_value, get_item_spec},
+};
+
+/// First counter used by the console if none is specified.
+const CONSOLE_START_COUNTER: i32 = 9000;
+
+/// Item edit that sets `target` to `operand * modifier`, or just `modifier` without an operand.
+fn set_item(cfg: &GDObjConfig, operand: Option<Item>, target: Item, modifier: f64) -> GDObject {
+    modify_item(
+        cfg,
+        operand,
+        target,
+        modifier,
+        Op::Set,
+        true,
+        RoundMode::None,
+    )
+}
+
+fn modify_item(
+    cfg: &GDObjConfig,
+    operand: Option<Item>,
+    target: Item,
+    modifier: f64,
+    op: Op,
+    multiply: bool,
+    rounding: RoundMode,
+) -> GDObject {
+    item_edit(
+        cfg,
+        operand,
+        None,
+        target,
+        modifier,
+        op,
+        multiply,
+        None,
+        RoundMode::None,
+        rounding,
+        SignMode::None,
+        SignMode::None,
+    )
+}
+
+/// Returns a compare that activates `group` if `item` compares true against `value`.
+fn compare_literal(
+    cfg: &GDObjConfig,
+    group: i16,
+    item: Item,
+    op: CompareOp,
+    value: f64,
+) -> GDObject {
+    item_compare(
+        cfg,
+        group,
+        0,
+        item.into(),
+        CompareOperand::number_literal(value),
+        op,
+        0.0,
+    )
+}
+
+pub fn console(args: HandlerArgs) -> HandlerReturn {
+    let rows = args.args[0].to_int().unwrap();
+    let cols = args.args[1].to_int().unwrap();
+    let start_ctr = args
+        .args
+        .get(2)
+        .and_then(|a| a.to_int())
+        .unwrap_or(CONSOLE_START_COUNTER);
+
+    let cells = rows * cols;
+    // the cursor and register come right after the cells
+    let end_ctr = start_ctr + cells + 1;
+    if rows < 1 || cols < 1 || start_ctr < 1 || end_ctr > GROUP_LIMIT as i32 {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            format!("Cannot create a {rows}x{cols} console on items {start_ctr} to {end_ctr}."),
+        ));
+    }
+
+    let timefmt: bool = get_flag_value(&args, "timefmt", FlagValue::Bool(false)).into();
+    let align: ItemAlign =
+        get_flag_value(&args, "align", FlagValue::Align(ItemAlign::Center)).into();
+    // timers keep decimals and are displayed in time format
+    let item_constructor = match timefmt {
+        true => Item::Timer,
+        false => Item::Counter,
+    };
+    let cursor = Item::Counter((start_ctr + cells) as i16);
+    let register = item_constructor(end_ctr as i16);
+
+    // top left cell. by default, the console is placed to the left of the collision blocks
+    let x0: f64 =
+        get_flag_value(&args, "xpos", FlagValue::Float(-165.0 - 30.0 * cols as f64)).into();
+    let y0: f64 = get_flag_value(
+        &args,
+        "ypos",
+        FlagValue::Float(75.0 + 30.0 * (rows - 1) as f64),
+    )
+    .into();
+
+    // group layout:
+    // * write group: compares the cursor to each cell
+    // * newline group
+    // * clear group
+    // * wrap group: moves the cursor back to the first cell
+    // * display group: contains every cell display
+    // * then, for each cell, one group to write to it and one group for its display
+    let write_group = args.curr_group;
+    let newline_group = write_group + 1;
+    let clear_group = write_group + 2;
+    let wrap_group = write_group + 3;
+    let display_group = write_group + 4;
+    let cell_group = |i: i32| write_group + 5 + 2 * i as i16;
+
+    let shared_cfg = |group: i16| {
+        GDObjConfig::new()
+            .groups([group])
+            .spawnable(true)
+            .multitrigger(true)
+    };
+    // controller triggers go below the cells
+    let ctrl_y = y0 - 30.0 * rows as f64;
+
+    let prefix = match timefmt {
+        true => 'T',
+        false => 'C',
+    };
+    let mut objs = vec![text(
+        &GDObjConfig::new().pos(x0, y0 + 30.0).scale(0.5, 0.5),
+        format!(
+            "console: {prefix}{start_ctr}-{prefix}{}",
+            start_ctr + cells - 1
+        ),
+        0,
+    )];
+
+    for i in 0..cells {
+        let (row, col) = (i / cols, i % cols);
+        let pos = (x0 + 30.0 * col as f64, y0 - 30.0 * row as f64);
+        let cell = item_constructor((start_ctr + i) as i16);
+        let write_cell = cell_group(i);
+        let show_cell = write_cell + 1;
+
+        objs.push(counter_object(
+            &GDObjConfig::new()
+                .pos(pos.0, pos.1)
+                .scale(0.5, 0.5)
+                .groups([show_cell, display_group]),
+            cell,
+            align,
+            false,
+        ));
+
+        // compare the cursor to this cell
+        objs.push(compare_literal(
+            &shared_cfg(write_group).pos(x0, ctrl_y - 30.0),
+            write_cell,
+            cursor,
+            CompareOp::Equals,
+            i as f64,
+        ));
+        // copy the register into the cell and show it
+        let write_cfg = shared_cfg(write_cell)
+            .pos(pos.0, pos.1 - 10.0)
+            .scale(0.3, 0.3);
+        objs.push(set_item(&write_cfg, Some(register), cell, 1.0));
+        objs.push(toggle_trigger(
+            &write_cfg.clone().translate(5.0, 0.0),
+            show_cell,
+            true,
+        ));
+    }
+
+    // advance the cursor after the write, then wrap it around
+    objs.push(modify_item(
+        &shared_cfg(write_group).pos(x0 + 1.0, ctrl_y - 30.0),
+        None,
+        cursor,
+        1.0,
+        Op::Add,
+        true,
+        RoundMode::None,
+    ));
+    objs.push(compare_literal(
+        &shared_cfg(write_group).pos(x0 + 2.0, ctrl_y - 30.0),
+        wrap_group,
+        cursor,
+        CompareOp::GreaterOrEquals,
+        cells as f64,
+    ));
+
+    // newline: cursor = (floor(cursor / cols) + 1) * cols
+    let newline_cfg = shared_cfg(newline_group).pos(x0, ctrl_y - 60.0);
+    objs.extend([
+        modify_item(
+            &newline_cfg,
+            Some(cursor),
+            cursor,
+            cols as f64,
+            Op::Set,
+            false,
+            RoundMode::Floor,
+        ),
+        modify_item(
+            &newline_cfg.clone().translate(1.0, 0.0),
+            None,
+            cursor,
+            1.0,
+            Op::Add,
+            true,
+            RoundMode::None,
+        ),
+        modify_item(
+            &newline_cfg.clone().translate(2.0, 0.0),
+            None,
+            cursor,
+            cols as f64,
+            Op::Mul,
+            true,
+            RoundMode::None,
+        ),
+        compare_literal(
+            &newline_cfg.clone().translate(3.0, 0.0),
+            wrap_group,
+            cursor,
+            CompareOp::GreaterOrEquals,
+            cells as f64,
+        ),
+    ]);
+
+    // clear: hide every cell and go back to the start
+    let clear_cfg = shared_cfg(clear_group).pos(x0, ctrl_y - 90.0);
+    objs.push(toggle_trigger(&clear_cfg, display_group, false));
+    objs.push(set_item(
+        &clear_cfg.clone().translate(1.0, 0.0),
+        None,
+        cursor,
+        0.0,
+    ));
+
+    objs.push(set_item(
+        &shared_cfg(wrap_group).pos(x0, ctrl_y - 120.0),
+        None,
+        cursor,
+        0.0,
+    ));
+
+    // cells are empty at the start of the level
+    objs.push(toggle_trigger(
+        &GDObjConfig::new()
+            .pos(-15.0, ctrl_y - 90.0)
+            .multitrigger(true),
+        display_group,
+        false,
+    ));
+
+    let mut data = HandlerData::from_objects(objs)
+        .skip_spaces(0)
+        .extra_groups(5 + 2 * cells as i16);
+    data.new_console = Some(ConsoleInfo {
+        register: match timefmt {
+            true => TasmValue::Timer(end_ctr as i16),
+            false => TasmValue::Counter(end_ctr as i16),
+        },
+        write_group,
+        newline_group,
+        clear_group,
+        items: start_ctr as i16..=end_ctr as i16,
+        line: args.line,
+    });
+    Ok(data)
+}
+
+pub fn console_print(args: HandlerArgs) -> HandlerReturn {
+    let console = args.console_info.unwrap();
+    let register = get_item_spec(&console.register).unwrap();
+    let set_register = match get_item_spec(&args.args[0]) {
+        Some(item) => set_item(&args.cfg, Some(item), register, 1.0),
+        None => set_item(&args.cfg, None, register, args.args[0].to_float().unwrap()),
+    };
+
+    // the write group needs one extra tick to finish
+    Ok(HandlerData::from_objects(vec![
+        set_register,
+        spawn_trg(&args.cfg.clone().translate(1.0, 0.0), console.write_group),
+    ])
+    .skip_spaces(2))
+}
+
+pub fn console_newline(args: HandlerArgs) -> HandlerReturn {
+    let console = args.console_info.unwrap();
+    Ok(HandlerData::from_objects(vec![spawn_trg(
+        &args.cfg,
+        console.newline_group,
+    )]))
+}
+
+pub fn console_clear(args: HandlerArgs) -> HandlerReturn {
+    let console = args.console_info.unwrap();
+    Ok(HandlerData::from_objects(vec![spawn_trg(
+        &args.cfg,
+        console.clear_group,
+    )]))
+}
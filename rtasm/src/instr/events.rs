@@ -0,0 +1,247 @@
+use gdlib::gdobj::{
+    Event, GDObject, GDValue,
+    ids::properties::{
+        ACTIVATE_GROUP, COUNT_TRIGGER_MULTI_ACTIVATE, INPUT_ITEM_1, TARGET_COUNT, TARGET_ITEM,
+    },
+    triggers::{
+        ColliderConfig, CompareOp, ExtraID2, collision_trigger, count_trigger, event_trigger,
+    },
+};
+use phf::phf_map;
+
+use crate::{
+    core::{
+        HandlerReturn,
+        error::{TasmError, TasmErrorType},
+        flags::FlagValue,
+        structs::{HandlerArgs, HandlerData},
+    },
+    instr::{
+        fns::{SpawnGroups, validate_collision_id},
+        get_flag_value, get_flag_value_opt,
+        ids::{INSTANT_COUNT_MODE, InstantCountMode, TRIGGER_INSTANT_COUNT},
+    },
+};
+
+/* COUNT */
+
+pub fn oncount(args: HandlerArgs) -> HandlerReturn {
+    // count triggers only work on counters
+    let item_id = match args.args[0].to_counter_id() {
+        Some(id) => id,
+        None => {
+            return Err(TasmError::at_line(
+                TasmErrorType::InvalidArguments,
+                args.line,
+                "Count triggers can only listen to counters.",
+            ));
+        }
+    };
+    let target_count = args.args[1].to_int().unwrap();
+    let spawning_group = args.args[2].to_group_id().unwrap();
+
+    let instant: bool = get_flag_value(&args, "instant", FlagValue::Bool(false)).into();
+    let multi: bool = get_flag_value(&args, "multi", FlagValue::Bool(false)).into();
+
+    // the comparison mode is only configurable on the instant count trigger
+    let mode = match get_flag_value_opt(&args, "op") {
+        None => InstantCountMode::Equals,
+        Some(_) if !instant => {
+            return Err(TasmError::at_line(
+                TasmErrorType::BadFlag,
+                args.line,
+                "The op flag can only be used on instant count events.",
+            ));
+        }
+        Some(op) => match op.to_compare_op() {
+            Some(CompareOp::Equals) => InstantCountMode::Equals,
+            Some(CompareOp::Greater) => InstantCountMode::Larger,
+            Some(CompareOp::Less) => InstantCountMode::Smaller,
+            _ => {
+                return Err(TasmError::at_line(
+                    TasmErrorType::BadFlag,
+                    args.line,
+                    "Count events only support the =, > and < operators.",
+                ));
+            }
+        },
+    };
+
+    let cfg = &args.cfg;
+    let count_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5).scale(0.5, 0.5);
+    // the count trigger activates the auxiliary group, which spawns the routine in order
+    let spawn_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 + 7.5).scale(0.5, 0.5);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut objs = vec![];
+    let aux_group = spawn_groups.get(&spawn_cfg, spawning_group, &mut objs);
+
+    let counter = if instant {
+        GDObject::new(
+            TRIGGER_INSTANT_COUNT,
+            &count_cfg,
+            vec![
+                (INPUT_ITEM_1, GDValue::Item(item_id)),
+                (TARGET_ITEM, GDValue::Group(aux_group)),
+                (TARGET_COUNT, GDValue::Int(target_count)),
+                (ACTIVATE_GROUP, GDValue::Bool(true)),
+                (INSTANT_COUNT_MODE, GDValue::Int(mode as i32)),
+            ],
+        )
+    } else {
+        let mut counter = count_trigger(&count_cfg, item_id, aux_group, target_count, true, multi);
+        // gdlib writes the generic multi-activate property, which the count trigger ignores
+        counter.set_property(COUNT_TRIGGER_MULTI_ACTIVATE, GDValue::Bool(multi));
+        counter
+    };
+
+    objs.insert(0, counter);
+    Ok(spawn_groups.finish(HandlerData::from_objects(objs)))
+}
+
+/* COLLISION */
+
+fn collision_event(args: HandlerArgs, on_exit: bool) -> HandlerReturn {
+    let player1: bool = get_flag_value(&args, "p1", FlagValue::Bool(false)).into();
+    let player2: bool = get_flag_value(&args, "p2", FlagValue::Bool(false)).into();
+
+    // argsets: (block, block, routine), (block, routine) and (routine)
+    let iargs = args.args.as_ref();
+    let spawning_group = iargs[iargs.len() - 1].to_group_id().unwrap();
+    let collider_cfg = match iargs.len() {
+        3 => {
+            if player1 || player2 {
+                return Err(TasmError::at_line(
+                    TasmErrorType::BadFlag,
+                    args.line,
+                    "Player collisions only take one collision block.",
+                ));
+            }
+            ColliderConfig::two_colliders(
+                validate_collision_id(&iargs[0], args.line)?,
+                validate_collision_id(&iargs[1], args.line)?,
+            )
+        }
+        2 => {
+            // the player takes the place of the first collider
+            let mut collider_cfg =
+                ColliderConfig::two_colliders(0, validate_collision_id(&iargs[0], args.line)?);
+            collider_cfg.collide_player1 = player1 || !player2;
+            collider_cfg.collide_player2 = player2;
+            collider_cfg
+        }
+        _ => {
+            // collision between both players, the player flags are irrelevant here
+            let mut collider_cfg = ColliderConfig::two_colliders(0, 0);
+            collider_cfg.collide_both_players = true;
+            collider_cfg
+        }
+    };
+
+    let cfg = &args.cfg;
+    let collision_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5).scale(0.5, 0.5);
+    let spawn_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 + 7.5).scale(0.5, 0.5);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut objs = vec![];
+    let aux_group = spawn_groups.get(&spawn_cfg, spawning_group, &mut objs);
+
+    objs.insert(
+        0,
+        collision_trigger(&collision_cfg, collider_cfg, aux_group, true, on_exit),
+    );
+    Ok(spawn_groups.finish(HandlerData::from_objects(objs)))
+}
+
+pub fn oncollide(args: HandlerArgs) -> HandlerReturn {
+    collision_event(args, false)
+}
+
+pub fn onexit(args: HandlerArgs) -> HandlerReturn {
+    collision_event(args, true)
+}
+
+/* GAME EVENTS */
+
+/// Event names accepted by `ONEVENT`, and the game events they listen to.
+pub const EVENT_NAMES: phf::Map<&'static str, &'static [Event]> = phf_map! {
+    "jump" => &[Event::NormalJump],
+    "landing" => &[
+        Event::TinyLanding,
+        Event::FeatherLanding,
+        Event::SoftLanding,
+        Event::NormalLanding,
+        Event::HardLanding,
+    ],
+    "tiny_landing" => &[Event::TinyLanding],
+    "feather_landing" => &[Event::FeatherLanding],
+    "soft_landing" => &[Event::SoftLanding],
+    "normal_landing" => &[Event::NormalLanding],
+    "hard_landing" => &[Event::HardLanding],
+    "hit_head" => &[Event::HitHead],
+    "orb" => &[Event::OrbActivated],
+    "pad" => &[Event::PadActivated],
+    "gravity_invert" => &[Event::GravityInverted],
+    "gravity_restore" => &[Event::GravityRestored],
+    "ufo_jump" => &[Event::UFOJump],
+    "robot_boost_start" => &[Event::RobotBoostStart],
+    "robot_boost_stop" => &[Event::RobotBoostStop],
+    "ship_boost_start" => &[Event::ShipBoostStart],
+    "ship_boost_stop" => &[Event::ShipBoostEnd],
+    "spider_teleport" => &[Event::SpiderTeleport],
+    "ball_switch" => &[Event::BallSwitch],
+    "swing_switch" => &[Event::SwingSwitch],
+    "wave_push" => &[Event::WavePush],
+    "wave_release" => &[Event::WaveRelease],
+    "dash_start" => &[Event::DashStart],
+    "dash_stop" => &[Event::DashStop],
+    "teleport" => &[Event::Teleported],
+    // any gamemode portal
+    "portal" => &[
+        Event::PortalNormal,
+        Event::PortalShip,
+        Event::PortalBall,
+        Event::PortalUFO,
+        Event::PortalWave,
+        Event::PortalRobot,
+        Event::PortalSpider,
+        Event::PortalSwing,
+    ],
+    "checkpoint" => &[Event::Checkpoint],
+    "respawn" => &[Event::CheckpointRespawn],
+    "destroy_block" => &[Event::DestroyBlock],
+    "coin" => &[Event::UserCoin],
+    "pickup" => &[Event::PickupItem],
+    "jump_push" => &[Event::JumpPush],
+    "jump_release" => &[Event::JumpRelease],
+    "left_push" => &[Event::LeftPush],
+    "left_release" => &[Event::LeftRelease],
+    "right_push" => &[Event::RightPush],
+    "right_release" => &[Event::RightRelease],
+    "reversed" => &[Event::PlayerReversed],
+};
+
+pub fn onevent(args: HandlerArgs) -> HandlerReturn {
+    // event names are validated by the lexer
+    let events = EVENT_NAMES
+        .get(args.args[0].to_string().unwrap().as_str())
+        .unwrap()
+        .to_vec();
+    let spawning_group = args.args[1].to_group_id().unwrap();
+
+    let player1: bool = get_flag_value(&args, "p1", FlagValue::Bool(false)).into();
+    let player2: bool = get_flag_value(&args, "p2", FlagValue::Bool(false)).into();
+    let player = match (player1, player2) {
+        (true, false) => ExtraID2::P1,
+        (false, true) => ExtraID2::P2,
+        _ => ExtraID2::All,
+    };
+
+    let cfg = &args.cfg;
+    let event_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5).scale(0.5, 0.5);
+    let spawn_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 + 7.5).scale(0.5, 0.5);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut objs = vec![];
+    let aux_group = spawn_groups.get(&spawn_cfg, spawning_group, &mut objs);
+
+    objs.insert(0, event_trigger(&event_cfg, aux_group, events, 0, player));
+    Ok(spawn_groups.finish(HandlerData::from_objects(objs)))
+}
@@ -1,26 +1,33 @@
 use gdlib::gdobj::{
-    GDObjConfig, GDObject, GDValue, Group, ItemType, ZLayer,
-    ids::properties::{TARGET_ITEM, TARGET_ITEM_2},
+    GDObjConfig, GDObject, GDValue, Group, Item, ItemType, ZLayer,
     misc::{default_block, text},
     triggers::{
         CompareOp, CompareOperand, ItemAlign, Op, RoundMode, SignMode, StopMode, TimeTriggerConfig,
-        counter_object, item_compare, item_edit, persistent_item, random_trigger, spawn_trigger,
-        stop_trigger, time_control, time_trigger, toggle_trigger,
+        advanced_random_trigger, collision_block, counter_object, group_reset, item_compare,
+        item_edit, persistent_item, random_trigger, spawn_trigger, stop_trigger, time_control,
+        time_trigger, toggle_trigger,
     },
 };
 
+use alloc::borrow::Cow;
 use paste::paste;
+use std::collections::HashMap;
 
 use crate::{
     core::{
         HandlerReturn,
+        consts::{GROUP_LIMIT, TRACE_LAST_COUNTER},
         error::{TasmError, TasmErrorType},
         flags::FlagValue,
-        structs::{HandlerArgs, HandlerData},
+        structs::{DebugInfo, HandlerArgs, HandlerData, Routine, TasmValue},
     },
     instr::{
         GROUP_SPAWN_DELAY, LowerCompOp, LowerOp, flag_override, get_flag_value, get_flag_value_opt,
         get_item_spec,
+        ids::{
+            SEQUENCE_MIN_INTERVAL, SEQUENCE_MODE, SEQUENCE_RESET, SEQUENCE_STEP_RESET,
+            SEQUENCE_STEPS, SequenceMode, TRIGGER_SEQUENCE,
+        },
     },
 };
 
@@ -39,19 +46,15 @@ macro_rules! handlers {
         )*
     };
 
-    ( [$($var:ident),* $(,)?] + $extra_groups:literal => $inner_fn:ident) => {
+    // auxiliary groups are handed out by the inner fn
+    ( [$($var:ident),* $(,)?] => $inner_fn:ident) => {
         $(
             paste! {
                 pub fn [<$inner_fn _ $var>](args: HandlerArgs) -> HandlerReturn {
-                    Ok(
-                        HandlerData::from_objects($inner_fn(args, (LowerCompOp::$var).to_op(), false))
-                            .extra_groups($extra_groups),
-                    )
+                    Ok($inner_fn(args, (LowerCompOp::$var).to_op(), false))
                 }
                 pub fn [<instant _ $inner_fn _ $var>](args: HandlerArgs) -> HandlerReturn {
-                    Ok(
-                        HandlerData::from_objects($inner_fn(args, (LowerCompOp::$var).to_op(), true)),
-                    )
+                    Ok($inner_fn(args, (LowerCompOp::$var).to_op(), true))
                 }
             }
         )*
@@ -71,6 +74,138 @@ pub fn skip(_args: HandlerArgs) -> HandlerReturn {
     Ok(HandlerData::default().skip_spaces(0))
 }
 
+/* DEBUG */
+
+pub fn breakpoint(args: HandlerArgs) -> HandlerReturn {
+    // breakpoints only do something in debug mode
+    let Some(debug) = args.debug_info else {
+        return skip(args);
+    };
+
+    // show which breakpoint was hit, then pause every routine, including this one
+    let mut objs = vec![item_edit(
+        &args.cfg,
+        None,
+        None,
+        Item::Counter(debug.line_counter),
+        (args.line + 1) as f64,
+        Op::Set,
+        true,
+        None,
+        RoundMode::None,
+        RoundMode::None,
+        SignMode::None,
+        SignMode::None,
+    )];
+    let pause_cfg = args.cfg.clone().translate(1.0, 0.0);
+    objs.extend(
+        debug
+            .routine_groups
+            .iter()
+            .map(|&group| stop_trigger(&pause_cfg, group, StopMode::Pause, true)),
+    );
+
+    Ok(HandlerData::from_objects(objs))
+}
+
+/// Item edit that sets `counter` to `value`, or adds `value` to it.
+fn trace_edit(cfg: &GDObjConfig, counter: i16, value: f64, op: Op) -> GDObject {
+    item_edit(
+        cfg,
+        None,
+        None,
+        Item::Counter(counter),
+        value,
+        op,
+        true,
+        None,
+        RoundMode::None,
+        RoundMode::None,
+        SignMode::None,
+        SignMode::None,
+    )
+}
+
+/// Labelled counter display of the trace panel, which is placed below the level.
+fn trace_display(row: i16, counter: i16, label: String) -> [GDObject; 2] {
+    let y = -15.0 - 30.0 * row as f64;
+    [
+        text(&GDObjConfig::new().pos(45.0, y).scale(0.5, 0.5), label, 0),
+        counter_object(
+            &GDObjConfig::new().pos(105.0, y).scale(0.5, 0.5),
+            Item::Counter(counter),
+            ItemAlign::Center,
+            false,
+        ),
+    ]
+}
+
+/// Display of the group of the last routine entered.
+pub fn trace_header() -> [GDObject; 2] {
+    trace_display(0, TRACE_LAST_COUNTER, "last routine".into())
+}
+
+/// Counts activations of the routine and marks it as the last routine entered.
+pub fn trace_routine(routine: &Routine, group_offset: i16, pos: (f64, f64)) -> Vec<GDObject> {
+    let row = routine.group - group_offset;
+    let counter = TRACE_LAST_COUNTER - row;
+    // executed along with the first instruction of the routine
+    let cfg = GDObjConfig::new()
+        .pos(pos.0, pos.1)
+        .groups([routine.group])
+        .spawnable(true)
+        .multitrigger(true);
+
+    let mut objs = vec![
+        trace_edit(&cfg, counter, 1.0, Op::Add),
+        trace_edit(&cfg, TRACE_LAST_COUNTER, routine.group as f64, Op::Set),
+    ];
+    objs.extend(trace_display(row, counter, routine.ident.clone()));
+    objs
+}
+
+/// Objects shared by all breakpoints: the continue block and the line indicator.
+pub fn debug_controls(debug: &DebugInfo) -> Vec<GDObject> {
+    let resume_cfg = GDObjConfig::new()
+        .pos(45.0, 45.0)
+        .scale(0.3, 0.3)
+        .groups([debug.continue_group])
+        .spawnable(true)
+        .multitrigger(true);
+    let mut objs = debug
+        .routine_groups
+        .iter()
+        .map(|&group| stop_trigger(&resume_cfg, group, StopMode::Resume, true))
+        .collect::<Vec<_>>();
+
+    // placed right before the start block
+    objs.extend(
+        ioblock(HandlerArgs {
+            args: Cow::Owned(vec![
+                TasmValue::Group(debug.continue_group),
+                TasmValue::Number(-1.0),
+                TasmValue::String("continue".into()),
+            ]),
+            ..Default::default()
+        })
+        .unwrap()
+        .objects,
+    );
+    // the indicator goes right below the displays
+    objs.push(text(
+        &GDObjConfig::new().pos(-75.0, 30.0).scale(0.25, 0.25),
+        "breakpoint",
+        0,
+    ));
+    objs.push(counter_object(
+        &GDObjConfig::new().pos(-75.0, 45.0).scale(0.5, 0.5),
+        Item::Counter(debug.line_counter),
+        ItemAlign::Center,
+        false,
+    ));
+    objs
+}
+
 /* WAIT */
 
 pub fn nop(_args: HandlerArgs) -> HandlerReturn {
@@ -89,7 +224,9 @@ fn wait_internal(ticks: i32, line: usize) -> HandlerReturn {
             routine: String::new(),
             error: true,
             line,
+            span: None,
             details: "Cannot wait a negative number of ticks.".to_string(),
+            help: None,
         })
     }
 }
@@ -363,13 +500,66 @@ pub fn spawn_trg(spawn_cfg: &GDObjConfig, group: i16) -> GDObject {
     )
 }
 
+/// Hands out auxiliary groups that hold a single spawn trigger for a routine.
+/// These only depend on the routine, so every instruction that spawns the same routine
+/// through an auxiliary group shares the same one.
+pub struct SpawnGroups<'a> {
+    shared: Option<&'a HashMap<i16, i16>>,
+    next_group: i16,
+    created: Vec<(i16, i16)>,
+    reused: usize,
+}
+
+impl<'a> SpawnGroups<'a> {
+    pub fn new(args: &HandlerArgs<'a>) -> Self {
+        Self {
+            shared: args.spawn_groups,
+            next_group: args.curr_group,
+            created: vec![],
+            reused: 0,
+        }
+    }
+
+    /// Returns the auxiliary group that spawns `group`.
+    /// The first time a group is used, its spawn trigger is pushed to `objs`, placed with `cfg`.
+    pub fn get(&mut self, cfg: &GDObjConfig, group: i16, objs: &mut Vec<GDObject>) -> i16 {
+        if let Some(&aux) = self.shared.and_then(|s| s.get(&group)) {
+            self.reused += 1;
+            return aux;
+        }
+        if let Some(&(_, aux)) = self.created.iter().find(|&&(g, _)| g == group) {
+            return aux;
+        }
+
+        let aux = self.next_group;
+        self.next_group += 1;
+        self.created.push((group, aux));
+        // use auxiliary group for spawn trigger, which may also be used from `_init`
+        let spawn_cfg = cfg
+            .clone()
+            .groups([aux])
+            .spawnable(true)
+            .set_control_id(group);
+        objs.push(spawn_trg(&spawn_cfg, group));
+        aux
+    }
+
+    /// Records the auxiliary groups that were created and shared in the handler data.
+    pub fn finish(self, data: HandlerData) -> HandlerData {
+        let mut data = data.extra_groups(self.created.len() as i16);
+        data.new_spawn_groups = self.created;
+        data.shared_groups = self.reused;
+        data
+    }
+}
+
 pub fn spawn_compare(
     args: HandlerArgs,
     op: CompareOp,
     instant: bool,
     num_2nd_arg: bool,
-) -> Vec<GDObject> {
-    let cfg = args.cfg;
+) -> HandlerData {
+    let cfg = &args.cfg;
     let scale = match instant {
         false => 0.5,
         true => 1.0,
@@ -388,37 +578,43 @@ pub fn spawn_compare(
     };
 
     let spawning_group = iargs[0].to_group_id().unwrap();
-    let spawn_cfg = cfg
-        .clone()
-        .pos(cfg.pos.0, cfg.pos.1 + 7.5)
-        .scale(0.5, 0.5)
-        .groups([args.curr_group])
-        .set_control_id(spawning_group); // use auxiliary group for spawn trigger
-
-    let mut compare = item_compare(
-        &compare_cfg,
-        args.curr_group, // spawn auxiliary group (spawn trigger)
-        0,
-        lhs.into(),
-        rhs,
-        op,
-        0.0,
-    );
-
     if instant {
-        // override spawn group
-        compare.set_property(TARGET_ITEM, GDValue::Group(spawning_group));
         // don't use any intermediate triggers if spawning instantly
-        vec![compare]
-    } else {
-        vec![compare, spawn_trg(&spawn_cfg, spawning_group)]
+        return HandlerData::from_objects(vec![item_compare(
+            &compare_cfg,
+            spawning_group,
+            0,
+            lhs.into(),
+            rhs,
+            op,
+            0.0,
+        )]);
     }
+
+    let spawn_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 + 7.5).scale(0.5, 0.5);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut objs = vec![];
+    let aux_group = spawn_groups.get(&spawn_cfg, spawning_group, &mut objs);
+
+    objs.insert(
+        0,
+        item_compare(
+            &compare_cfg,
+            aux_group, // spawn auxiliary group (spawn trigger)
+            0,
+            lhs.into(),
+            rhs,
+            op,
+            0.0,
+        ),
+    );
+    spawn_groups.finish(HandlerData::from_objects(objs))
 }
 
-pub fn spawn_item_item(args: HandlerArgs, op: CompareOp, instant: bool) -> Vec<GDObject> {
+pub fn spawn_item_item(args: HandlerArgs, op: CompareOp, instant: bool) -> HandlerData {
     spawn_compare(args, op, instant, false)
 }
-pub fn spawn_item_num(args: HandlerArgs, op: CompareOp, instant: bool) -> Vec<GDObject> {
+pub fn spawn_item_num(args: HandlerArgs, op: CompareOp, instant: bool) -> HandlerData {
     spawn_compare(args, op, instant, true)
 }
 
@@ -427,9 +623,9 @@ pub fn fork_compare(
     op: CompareOp,
     instant: bool,
     num_2nd_arg: bool,
-) -> Vec<GDObject> {
+) -> HandlerData {
     // args for a fork compare: true, false, lhs, rhs
-    let cfg = args.cfg;
+    let cfg = &args.cfg;
     let scale = match instant {
         false => 0.33,
         true => 1.0,
@@ -446,84 +642,80 @@ pub fn fork_compare(
 
     let spawning_true = iargs[0].to_group_id().unwrap();
     let spawning_false = iargs[1].to_group_id().unwrap();
+    if instant {
+        // don't use any intermediate triggers if spawning instantly
+        return HandlerData::from_objects(vec![item_compare(
+            &compare_cfg,
+            spawning_true,
+            spawning_false,
+            lhs.into(),
+            rhs,
+            op,
+            0.0,
+        )]);
+    }
+
     let spawn_true_cfg = cfg
         .clone()
         .pos(cfg.pos.0, cfg.pos.1 + 10.0)
-        .scale(0.33, 0.33)
-        .groups([args.curr_group])
-        .set_control_id(spawning_true); // use auxiliary group for spawn trigger
-
+        .scale(0.33, 0.33);
     let spawn_false_cfg = cfg
         .clone()
         .pos(cfg.pos.0, cfg.pos.1 - 10.0)
-        .scale(0.33, 0.33)
-        .groups([args.curr_group + 1])
-        .set_control_id(spawning_false); // use auxiliary group for spawn trigger
-
-    let mut compare = item_compare(
-        &compare_cfg,
-        args.curr_group,     // spawn auxiliary group (true trigger)
-        args.curr_group + 1, // spawn 2nd aux group (false trigger)
-        lhs.into(),
-        rhs,
-        op,
-        0.0,
-    );
+        .scale(0.33, 0.33);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut objs = vec![];
+    let aux_true = spawn_groups.get(&spawn_true_cfg, spawning_true, &mut objs);
+    let aux_false = spawn_groups.get(&spawn_false_cfg, spawning_false, &mut objs);
 
-    if instant {
-        // override spawn group
-        compare.set_property(TARGET_ITEM, GDValue::Group(spawning_true));
-        compare.set_property(TARGET_ITEM_2, GDValue::Group(spawning_false));
-        // don't use any intermediate triggers if spawning instantly
-        vec![compare]
-    } else {
-        vec![
-            compare,
-            spawn_trg(&spawn_true_cfg, spawning_true),
-            spawn_trg(&spawn_false_cfg, spawning_false),
-        ]
-    }
+    objs.insert(
+        0,
+        item_compare(
+            &compare_cfg,
+            aux_true,  // spawn auxiliary group (true trigger)
+            aux_false, // spawn 2nd aux group (false trigger)
+            lhs.into(),
+            rhs,
+            op,
+            0.0,
+        ),
+    );
+    spawn_groups.finish(HandlerData::from_objects(objs))
 }
 
-pub fn fork_item_num(args: HandlerArgs, op: CompareOp, instant: bool) -> Vec<GDObject> {
+pub fn fork_item_num(args: HandlerArgs, op: CompareOp, instant: bool) -> HandlerData {
     fork_compare(args, op, instant, true)
 }
-pub fn fork_item_item(args: HandlerArgs, op: CompareOp, instant: bool) -> Vec<GDObject> {
+pub fn fork_item_item(args: HandlerArgs, op: CompareOp, instant: bool) -> HandlerData {
     fork_compare(args, op, instant, false)
 }
 
-handlers!([eq, ne, le, leq, ge, geq] + 1 => spawn_item_num);
-handlers!([eq, ne, le, leq, ge, geq] + 1 => spawn_item_item);
-handlers!([eq, ne, le, leq, ge, geq] + 2 => fork_item_num);
-handlers!([eq, ne, le, leq, ge, geq] + 2 => fork_item_item);
+handlers!([eq, ne, le, leq, ge, geq] => spawn_item_num);
+handlers!([eq, ne, le, leq, ge, geq] => spawn_item_item);
+handlers!([eq, ne, le, leq, ge, geq] => fork_item_num);
+handlers!([eq, ne, le, leq, ge, geq] => fork_item_item);
 
 /* RANDOMS */
 
 pub fn spawn_random(args: HandlerArgs) -> HandlerReturn {
-    let cfg = args.cfg;
+    let cfg = &args.cfg;
     let random_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5).scale(0.5, 0.5);
 
     let iargs = args.args.as_ref();
     let spawning_group = iargs[0].to_group_id().unwrap();
     let chance = iargs[1].to_float().unwrap();
 
-    let aux_group = args.curr_group;
-    let spawn_cfg = cfg
-        .clone()
-        .pos(cfg.pos.0, cfg.pos.1 + 7.5)
-        .scale(0.5, 0.5)
-        .groups([aux_group])
-        .set_control_id(spawning_group); // use auxiliary group for spawn trigger
+    let spawn_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 + 7.5).scale(0.5, 0.5);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut objs = vec![];
+    let aux_group = spawn_groups.get(&spawn_cfg, spawning_group, &mut objs);
 
-    Ok(HandlerData::from_objects(vec![
-        random_trigger(&random_cfg, chance, aux_group, 0),
-        spawn_trg(&spawn_cfg, spawning_group),
-    ])
-    .extra_groups(1))
+    objs.insert(0, random_trigger(&random_cfg, chance, aux_group, 0));
+    Ok(spawn_groups.finish(HandlerData::from_objects(objs)))
 }
 
 pub fn fork_random(args: HandlerArgs) -> HandlerReturn {
-    let cfg = args.cfg;
+    let cfg = &args.cfg;
     let random_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5).scale(0.5, 0.5);
 
     let iargs = args.args.as_ref();
@@ -531,27 +723,18 @@ pub fn fork_random(args: HandlerArgs) -> HandlerReturn {
     let spawning_group2 = iargs[1].to_group_id().unwrap();
     let chance = iargs[2].to_float().unwrap();
 
-    let aux_group1 = args.curr_group;
-    let aux_group2 = args.curr_group + 1;
-    let spawn_cfg1 = cfg
-        .clone()
-        .pos(cfg.pos.0, cfg.pos.1 + 7.5)
-        .scale(0.5, 0.5)
-        .groups([aux_group1])
-        .set_control_id(spawning_group1); // use auxiliary group for spawn trigger
-    let spawn_cfg2 = cfg
-        .clone()
-        .pos(cfg.pos.0, cfg.pos.1 - 7.5)
-        .scale(0.5, 0.5)
-        .groups([aux_group2])
-        .set_control_id(spawning_group2); // use auxiliary group for spawn trigger
+    let spawn_cfg1 = cfg.clone().pos(cfg.pos.0, cfg.pos.1 + 7.5).scale(0.5, 0.5);
+    let spawn_cfg2 = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5).scale(0.5, 0.5);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut objs = vec![];
+    let aux_group1 = spawn_groups.get(&spawn_cfg1, spawning_group1, &mut objs);
+    let aux_group2 = spawn_groups.get(&spawn_cfg2, spawning_group2, &mut objs);
 
-    Ok(HandlerData::from_objects(vec![
+    objs.insert(
+        0,
         random_trigger(&random_cfg, chance, aux_group1, aux_group2),
-        spawn_trg(&spawn_cfg1, spawning_group1),
-        spawn_trg(&spawn_cfg2, spawning_group2),
-    ])
-    .extra_groups(2))
+    );
+    Ok(spawn_groups.finish(HandlerData::from_objects(objs)))
 }
 
 pub fn instant_spawn_random(args: HandlerArgs) -> HandlerReturn {
@@ -587,6 +770,153 @@ pub fn instant_fork_random(args: HandlerArgs) -> HandlerReturn {
     )]))
 }
 
+/// Validates the outcomes of a weighted random instruction.
+fn weighted_outcomes(args: &HandlerArgs) -> Result<Vec<(i16, i16)>, TasmError> {
+    let outcomes = args.args[0].to_dict().unwrap().clone();
+
+    if outcomes.is_empty() {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            "Weighted random spawns need at least one outcome.",
+        ));
+    }
+    if outcomes.iter().any(|&(_, weight)| weight < 0) {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            "Outcome weights can not be negative.",
+        ));
+    }
+    if outcomes.iter().all(|&(_, weight)| weight == 0) {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            "At least one outcome weight must be larger than 0.",
+        ));
+    }
+    Ok(outcomes)
+}
+
+pub fn spawn_random_weighted(args: HandlerArgs) -> HandlerReturn {
+    let outcomes = weighted_outcomes(&args)?;
+    let cfg = args.cfg.clone();
+
+    let random_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5).scale(0.5, 0.5);
+    let spawn_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 + 7.5).scale(0.5, 0.5);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut probabilities = vec![];
+    let mut objs = vec![];
+    for &(spawning_group, weight) in &outcomes {
+        let aux_group = spawn_groups.get(&spawn_cfg, spawning_group, &mut objs);
+        probabilities.push((aux_group, weight as i32));
+    }
+    objs.insert(0, advanced_random_trigger(&random_cfg, probabilities));
+
+    Ok(spawn_groups.finish(HandlerData::from_objects(objs)))
+}
+
+pub fn instant_spawn_random_weighted(args: HandlerArgs) -> HandlerReturn {
+    let outcomes = weighted_outcomes(&args)?;
+    let cfg = args.cfg.clone();
+
+    let random_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5);
+    wrap_objs!(vec![advanced_random_trigger(
+        &random_cfg,
+        outcomes.iter().map(|&(g, w)| (g, w as i32)).collect()
+    )])
+}
+
+/* SEQUENCES */
+
+pub fn sequence(args: HandlerArgs) -> HandlerReturn {
+    // each step is a group with the amount of activations it takes, which is 1 in a list
+    let steps = args.args[0].to_dict().unwrap().clone();
+    if steps.is_empty() {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            "Sequences need at least one step.",
+        ));
+    }
+    if steps.iter().any(|&(_, count)| count < 1) {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            "Each step of a sequence must be activated at least once.",
+        ));
+    }
+
+    let mode = match args.args.get(1).map(|m| m.to_string().unwrap()).as_deref() {
+        None | Some("stop") => SequenceMode::Stop,
+        Some("loop") => SequenceMode::Loop,
+        Some("last") => SequenceMode::Last,
+        Some(mode) => {
+            return Err(TasmError::at_line(
+                TasmErrorType::InvalidArguments,
+                args.line,
+                format!("Unknown sequence mode {mode}."),
+            )
+            .with_help("Use stop, loop or last."));
+        }
+    };
+
+    let interval: f64 = get_flag_value(&args, "interval", FlagValue::Float(0.0)).into();
+    let reset: f64 = get_flag_value(&args, "reset", FlagValue::Float(0.0)).into();
+    if interval < 0.0 || reset < 0.0 {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            "The interval and reset of a sequence can not be negative.",
+        ));
+    }
+
+    let cfg = args.cfg.clone();
+    let sequence_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 - 7.5).scale(0.5, 0.5);
+
+    // every distinct group gets an auxiliary group, which spawns the routine in order
+    let spawn_cfg = cfg.clone().pos(cfg.pos.0, cfg.pos.1 + 7.5).scale(0.5, 0.5);
+    let mut spawn_groups = SpawnGroups::new(&args);
+    let mut objs = vec![];
+    let aux_steps = steps
+        .iter()
+        .map(|&(spawning_group, count)| {
+            let aux_group = spawn_groups.get(&spawn_cfg, spawning_group, &mut objs);
+            (aux_group, count as i32)
+        })
+        .collect();
+
+    objs.insert(
+        0,
+        GDObject::new(
+            TRIGGER_SEQUENCE,
+            &sequence_cfg,
+            vec![
+                (SEQUENCE_STEPS, GDValue::from_prob_list(aux_steps)),
+                (SEQUENCE_MODE, GDValue::Int(mode as i32)),
+                (SEQUENCE_MIN_INTERVAL, GDValue::Float(interval)),
+                (SEQUENCE_RESET, GDValue::Float(reset)),
+                (
+                    SEQUENCE_STEP_RESET,
+                    GDValue::Bool(
+                        get_flag_value(&args, "stepreset", FlagValue::Bool(false)).into(),
+                    ),
+                ),
+            ],
+        ),
+    );
+
+    Ok(spawn_groups.finish(HandlerData::from_objects(objs)))
+}
+
+pub fn sequence_reset(args: HandlerArgs) -> HandlerReturn {
+    // resetting the routine group resets the progress of every sequence in it
+    Ok(HandlerData::from_objects(vec![group_reset(
+        &args.cfg,
+        args.args[0].to_group_id().unwrap(),
+    )]))
+}
+
 /* PROCESS */
 
 pub fn spawn(args: HandlerArgs) -> HandlerReturn {
@@ -684,6 +1014,47 @@ pub fn display(args: HandlerArgs) -> HandlerReturn {
         .added_item_display())
 }
 
+/// Checks that a collision block ID is within the valid ID range.
+pub fn validate_collision_id(id: &TasmValue, line: usize) -> Result<i16, TasmError> {
+    match id.to_int() {
+        Some(id) if id > 0 && id <= GROUP_LIMIT as i32 => Ok(id as i16),
+        _ => Err(TasmError::at_line(
+            TasmErrorType::BadID,
+            line,
+            format!("Collision block IDs must be within the range [1, {GROUP_LIMIT}]"),
+        )),
+    }
+}
+
+pub fn colblock(args: HandlerArgs) -> HandlerReturn {
+    let id = validate_collision_id(&args.args[0], args.line)?;
+    // collision blocks are placed in a column to the left of the displays
+    let (x, y) = (-135.0, 75.0 + 30.0 * args.colblocks as f64);
+    let mut cfg = GDObjConfig::new().pos(x, y);
+    if let Some(group) = args.args.get(1).and_then(|g| g.to_group_id()) {
+        // lets other triggers move the block around
+        cfg = cfg.groups([group]);
+    }
+
+    Ok(HandlerData::from_objects(vec![
+        collision_block(
+            &cfg,
+            id,
+            get_flag_value(&args, "dynamic", FlagValue::Bool(false)).into(),
+        ),
+        text(
+            &GDObjConfig::new()
+                .pos(x, y)
+                .scale(0.25, 0.25)
+                .set_z_layer(ZLayer::T2),
+            id.to_string(),
+            0,
+        ),
+    ])
+    .skip_spaces(0)
+    .added_colblock())
+}
+
 pub fn ioblock(args: HandlerArgs) -> HandlerReturn {
     let spawn_group = args.args[0].to_group_id().unwrap();
     let position = args.args[1].to_int().unwrap();
@@ -713,6 +1084,104 @@ pub fn ioblock(args: HandlerArgs) -> HandlerReturn {
     .skip_spaces(0))
 }
 
+pub fn input(args: HandlerArgs) -> HandlerReturn {
+    let item = get_item_spec(&args.args[0]).unwrap();
+    let label = args.args[1].to_string().unwrap();
+    let enter_group = args.args[2].to_group_id().unwrap();
+
+    // keypads are stacked in rows right below the IOBlock row, or below the trace panel if there is one.
+    // keys: digits 0-9, then backspace, then enter
+    let y = 15.0 - args.trace_height - 60.0 * args.inputs as f64;
+    let key_x = |key: usize| 75.0 + 30.0 * key as f64;
+    let key_cfg = |key: usize| GDObjConfig::new().pos(key_x(key), y);
+    let touch_cfg = |key: usize| key_cfg(key).touchable(true).multitrigger(true);
+    let text_cfg = |key: usize| key_cfg(key).scale(0.25, 0.25).set_z_layer(ZLayer::T2);
+
+    let mut objs = vec![text(
+        &GDObjConfig::new()
+            .pos(key_x(0) + 165.0, y + 30.0)
+            .scale(0.5, 0.5),
+        label,
+        0,
+    )];
+
+    // each digit gets an auxiliary group: item = item * 10 + digit
+    for digit in 0..10 {
+        let digit_group = args.curr_group + digit as i16;
+        let edit_cfg = key_cfg(digit)
+            .translate(0.0, -10.0)
+            .scale(0.3, 0.3)
+            .groups([digit_group])
+            .spawnable(true)
+            .multitrigger(true);
+
+        objs.extend([
+            default_block(&key_cfg(digit)),
+            spawn_trg(&touch_cfg(digit).set_control_id(digit_group), digit_group),
+            item_edit(
+                &edit_cfg.clone().translate(-7.5, 0.0),
+                None,
+                None,
+                item,
+                10.0,
+                Op::Mul,
+                true,
+                None,
+                RoundMode::None,
+                RoundMode::None,
+                SignMode::None,
+                SignMode::None,
+            ),
+            item_edit(
+                &edit_cfg.translate(7.5, 0.0),
+                None,
+                None,
+                item,
+                digit as f64,
+                Op::Add,
+                true,
+                None,
+                RoundMode::None,
+                RoundMode::None,
+                SignMode::None,
+                SignMode::None,
+            ),
+            text(&text_cfg(digit), digit.to_string(), 0),
+        ]);
+    }
+
+    // backspace: item = floor(item / 10)
+    objs.extend([
+        default_block(&key_cfg(10)),
+        item_edit(
+            &touch_cfg(10),
+            Some(item),
+            None,
+            item,
+            10.0,
+            Op::Set,
+            false,
+            None,
+            RoundMode::None,
+            RoundMode::Floor,
+            SignMode::None,
+            SignMode::None,
+        ),
+        text(&text_cfg(10), "<", 0),
+    ]);
+
+    objs.extend([
+        default_block(&key_cfg(11)),
+        spawn_trg(&touch_cfg(11).set_control_id(enter_group), enter_group),
+        text(&text_cfg(11), "ok", 0),
+    ]);
+
+    Ok(HandlerData::from_objects(objs)
+        .skip_spaces(0)
+        .extra_groups(10)
+        .added_input())
+}
+
 pub fn pers(args: HandlerArgs) -> HandlerReturn {
     let item = get_item_spec(&args.args[0]).unwrap();
     Ok(HandlerData::from_objects(vec![persistent_item(
@@ -755,7 +1224,7 @@ pub fn raw_objs(args: HandlerArgs) -> HandlerReturn {
 
 pub fn raw_trigger(args: HandlerArgs) -> HandlerReturn {
     let (x, y) = args.cfg.pos;
-    let group = args.cfg.groups.get(0).unwrap_or(&Group::Regular(0));
+    let group = args.cfg.groups.first().unwrap_or(&Group::Regular(0));
     let objs = args.args[0]
         .to_string()
         .unwrap()
@@ -0,0 +1,324 @@
+use gdlib::gdobj::{
+    GDObjConfig, GDObject, Group, Item,
+    misc::{default_block, text},
+    triggers::{
+        CompareOp, CompareOperand, Op, RoundMode, SignMode, item_compare, item_edit, spawn_trigger,
+        toggle_trigger,
+    },
+};
+
+use crate::{
+    core::{
+        HandlerReturn,
+        consts::GROUP_LIMIT,
+        error::{TasmError, TasmErrorType},
+        structs::{FramebufferInfo, HandlerArgs, HandlerData, TasmValue},
+    },
+    instr::get_item_spec,
+};
+
+/// First counter used by the framebuffer if none is specified.
+const FRAMEBUFFER_START_COUNTER: i32 = 9900;
+
+/// Amount of bits needed to encode every coordinate in `[0, size)`.
+fn coord_bits(size: i32) -> i16 {
+    f64::log2(size as f64).ceil() as i16
+}
+
+pub fn framebuffer(args: HandlerArgs) -> HandlerReturn {
+    let iargs = args.args.as_ref();
+    let width = iargs[0].to_int().unwrap();
+    let height = iargs[1].to_int().unwrap();
+    // position of the bottom-left pixel, in blocks
+    let x = iargs[2].to_int().unwrap() as f64;
+    let y = iargs[3].to_int().unwrap() as f64;
+    let start_ctr = iargs
+        .get(4)
+        .and_then(|a| a.to_int())
+        .unwrap_or(FRAMEBUFFER_START_COUNTER);
+
+    if width < 1 || height < 1 {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            format!("Cannot create a framebuffer of size {width}x{height}."),
+        ));
+    }
+
+    let x_bits = coord_bits(width);
+    let y_bits = coord_bits(height);
+    // x register, y register, then one temporary counter per bit
+    let end_ctr = start_ctr + 1 + (x_bits + y_bits) as i32;
+    if start_ctr < 1 || end_ctr > GROUP_LIMIT as i32 {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            format!("Cannot use counters {start_ctr} to {end_ctr} for the framebuffer."),
+        ));
+    }
+    // five shared groups, four per bit and one per pixel, see the group layout below
+    let groups_needed = width as i64 * height as i64 + 4 * (x_bits + y_bits) as i64 + 5;
+    let groups_left = (GROUP_LIMIT - args.curr_group) as i64 + 1;
+    if groups_needed > groups_left {
+        return Err(TasmError::at_line(
+            TasmErrorType::InvalidArguments,
+            args.line,
+            format!(
+                "A framebuffer of size {width}x{height} needs {groups_needed} groups, but only {groups_left} are left."
+            ),
+        ));
+    }
+    let x_reg = start_ctr as i16;
+    let y_reg = x_reg + 1;
+
+    // group layout:
+    // * 0: controller group
+    // * 1: shared group, spawns the triggers of the pixel that is left
+    // * 2: on group
+    // * 3: off group
+    // * 4: pixels group, contains every pixel
+    // * 5..(5 + x_bits * 2): encoding for bits of the x-coordinate
+    // * then the encoding for bits of the y-coordinate, just like in `malloc_generator`
+    // * then two groups per bit for the controller's compares
+    // * then one group per pixel
+    let group_offset = args.curr_group;
+    let controller_group = group_offset;
+    let shared_group = group_offset + 1;
+    let on_group = group_offset + 2;
+    let off_group = group_offset + 3;
+    let pixels_group = group_offset + 4;
+    let x_bit_groups = group_offset + 5;
+    let y_bit_groups = x_bit_groups + x_bits * 2;
+    let mut next_group = y_bit_groups + y_bits * 2;
+
+    let encode = |coord: i32, bits: i16, base: i16| {
+        (0..bits)
+            .map(|bit| base + bit * 2 + ((coord >> bit) & 1) as i16)
+            .collect::<Vec<_>>()
+    };
+
+    let mut level = vec![];
+
+    // the controller goes above the pixels
+    let ctrl_x = x * 30.0 + 15.0;
+    let ctrl_y = (y + height as f64) * 30.0 + 45.0;
+    level.push(text(
+        &GDObjConfig::new()
+            .pos(ctrl_x, ctrl_y - 15.0)
+            .scale(0.5, 0.5),
+        format!("framebuffer: {width}x{height}"),
+        0,
+    ));
+
+    // toggle everything back on from the previous plot
+    for group in (on_group..=off_group).chain(x_bit_groups..next_group) {
+        level.push(toggle_trigger(
+            &GDObjConfig::new()
+                .pos(ctrl_x, ctrl_y)
+                .groups([controller_group])
+                .spawnable(true)
+                .multitrigger(true),
+            group,
+            true,
+        ));
+    }
+
+    // mask each bit of each coordinate
+    let axes = [(x_reg, x_bits, x_bit_groups), (y_reg, y_bits, y_bit_groups)];
+    let mut temp_ctr = y_reg + 1;
+    let mut row = 1.0;
+    for (reg, bits, base) in axes {
+        for bit in 0..bits {
+            let mut cfg = GDObjConfig::new()
+                .pos(ctrl_x + 1.0, ctrl_y + row * 30.0)
+                .groups([controller_group])
+                .spawnable(true)
+                .multitrigger(true);
+
+            // isolate bit
+            let temp = Item::Counter(temp_ctr);
+            level.push(item_edit(
+                &cfg,
+                Some(Item::Counter(reg)),
+                None,
+                temp,
+                (bit as f64).exp2(),
+                Op::Set,
+                false,
+                None,
+                RoundMode::Floor,
+                RoundMode::None,
+                SignMode::None,
+                SignMode::None,
+            ));
+            level.push(item_compare(
+                &cfg.clone().translate(1.0, 0.0),
+                next_group,
+                next_group + 1,
+                CompareOperand {
+                    operand_item: temp,
+                    modifier: 2.0,
+                    mod_op: Op::Div,
+                    rounding: RoundMode::Floor,
+                    sign: SignMode::None,
+                },
+                CompareOperand {
+                    operand_item: temp,
+                    modifier: 2.0,
+                    mod_op: Op::Div,
+                    rounding: RoundMode::None,
+                    sign: SignMode::None,
+                },
+                CompareOp::Equals,
+                0.0,
+            ));
+
+            // even: toggle off the pixels where this bit is 1, and vice versa
+            cfg = cfg.translate(30.0, 0.0).groups([next_group]);
+            level.push(toggle_trigger(&cfg, base + bit * 2 + 1, false));
+            cfg = cfg.translate(30.0, 0.0).groups([next_group + 1]);
+            level.push(toggle_trigger(&cfg, base + bit * 2, false));
+
+            next_group += 2;
+            temp_ctr += 1;
+            row += 1.0;
+        }
+    }
+
+    for px in 0..width {
+        for py in 0..height {
+            let pixel_group = next_group;
+            next_group += 1;
+            let pos = ((x + px as f64) * 30.0 + 15.0, (y + py as f64) * 30.0 + 15.0);
+
+            level.push(default_block(
+                &GDObjConfig::new()
+                    .pos(pos.0, pos.1)
+                    .groups([pixel_group, pixels_group]),
+            ));
+
+            let mut groups = encode(px, x_bits, x_bit_groups);
+            groups.extend(encode(py, y_bits, y_bit_groups));
+            groups.push(shared_group);
+            let cfg = GDObjConfig::new()
+                .scale(0.2, 0.2)
+                .spawnable(true)
+                .multitrigger(true);
+
+            let mut on_cfg = cfg.clone().pos(pos.0 - 7.5, pos.1).groups(groups.clone());
+            on_cfg.add_group(Group::Regular(on_group));
+            level.push(toggle_trigger(&on_cfg, pixel_group, true));
+
+            let mut off_cfg = cfg.pos(pos.0 + 7.5, pos.1).groups(groups);
+            off_cfg.add_group(Group::Regular(off_group));
+            level.push(toggle_trigger(&off_cfg, pixel_group, false));
+        }
+    }
+
+    // pixels are off at the start of the level
+    level.push(toggle_trigger(
+        &GDObjConfig::new().pos(-15.0, ctrl_y).multitrigger(true),
+        pixels_group,
+        false,
+    ));
+
+    let mut data = HandlerData::from_objects(level)
+        .skip_spaces(0)
+        .extra_groups(next_group - args.curr_group);
+    data.new_fb = Some(FramebufferInfo {
+        x_reg,
+        y_reg,
+        controller_group,
+        shared_group,
+        on_group,
+        off_group,
+        pixels_group,
+        items: start_ctr as i16..=end_ctr as i16,
+        line: args.line,
+    });
+    Ok(data)
+}
+
+/// Item edit that copies a coordinate into its register.
+fn set_register(cfg: &GDObjConfig, coord: &TasmValue, reg: i16) -> GDObject {
+    let (operand, modifier) = match get_item_spec(coord) {
+        Some(item) => (Some(item), 1.0),
+        None => (None, coord.to_float().unwrap()),
+    };
+    item_edit(
+        cfg,
+        operand,
+        None,
+        Item::Counter(reg),
+        modifier,
+        Op::Set,
+        true,
+        None,
+        RoundMode::None,
+        RoundMode::None,
+        SignMode::None,
+        SignMode::None,
+    )
+}
+
+pub fn plot(args: HandlerArgs) -> HandlerReturn {
+    let fb = args.fb_info.unwrap();
+    let iargs = args.args.as_ref();
+
+    let on = match iargs.get(2).and_then(|s| s.to_string()).as_deref() {
+        None | Some("on") => true,
+        Some("off") => false,
+        Some(other) => {
+            return Err(TasmError::at_line(
+                TasmErrorType::InvalidArguments,
+                args.line,
+                format!("Pixels can only be plotted on or off, not {other}."),
+            ));
+        }
+    };
+
+    let cfg = &args.cfg;
+    Ok(HandlerData::from_objects(vec![
+        set_register(cfg, &iargs[0], fb.x_reg),
+        set_register(&cfg.clone().translate(1.0, 0.0), &iargs[1], fb.y_reg),
+        spawn_trigger(
+            &cfg.clone().translate(2.0, 0.0),
+            fb.controller_group,
+            0.0,
+            0.0,
+            false,
+            true,
+            false,
+            vec![],
+        ),
+        // only keep the triggers that do what we need
+        toggle_trigger(
+            &cfg.clone().translate(3.0, 0.0),
+            match on {
+                true => fb.off_group,
+                false => fb.on_group,
+            },
+            false,
+        ),
+        spawn_trigger(
+            &cfg.clone().translate(4.0, 0.0),
+            fb.shared_group,
+            0.0,
+            0.0,
+            false,
+            false,
+            false,
+            vec![],
+        ),
+    ])
+    .skip_spaces(5))
+}
+
+pub fn clear(args: HandlerArgs) -> HandlerReturn {
+    let fb = args.fb_info.unwrap();
+    Ok(HandlerData::from_objects(vec![toggle_trigger(
+        &args.cfg,
+        fb.pixels_group,
+        false,
+    )]))
+}
@@ -0,0 +1,49 @@
+//! Object and property IDs that are not (yet) exposed by gdlib.
+//! These are used to build triggers with `GDObject::new` directly.
+
+/* OBJECT IDS */
+pub const TRIGGER_INSTANT_COUNT: i32 = 1811;
+pub const TRIGGER_SFX: i32 = 3602;
+pub const TRIGGER_EDIT_SFX: i32 = 3603;
+pub const TRIGGER_EDIT_SONG: i32 = 3605;
+pub const TRIGGER_SEQUENCE: i32 = 3607;
+
+/* PROPERTY IDS */
+/// Comparison mode of the instant count trigger. See [`InstantCountMode`].
+pub const INSTANT_COUNT_MODE: u16 = 88;
+/// Pitch of the sound effect in semitones.
+pub const SFX_PITCH: u16 = 405;
+/// Whether or not only one instance of the sound effect may play at a time.
+pub const SFX_IS_UNIQUE: u16 = 415;
+/// ID used to reference the sound effect in edit SFX triggers.
+pub const SFX_UNIQUE_ID: u16 = 416;
+/// Stops the targeted song/sound effect in edit song/SFX triggers.
+pub const SOUND_STOP: u16 = 417;
+/// List of (group, count) steps of the sequence trigger.
+pub const SEQUENCE_STEPS: u16 = 435;
+/// What the sequence trigger does after the last step. See [`SequenceMode`].
+pub const SEQUENCE_MODE: u16 = 436;
+/// Minimum time in seconds between two steps of the sequence.
+pub const SEQUENCE_MIN_INTERVAL: u16 = 437;
+/// Time in seconds without activations after which the sequence resets.
+pub const SEQUENCE_RESET: u16 = 438;
+/// Only go back one step on reset instead of resetting the entire sequence.
+pub const SEQUENCE_STEP_RESET: u16 = 439;
+
+/// Comparison modes supported by the instant count trigger.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstantCountMode {
+    Equals = 0,
+    Larger = 1,
+    Smaller = 2,
+}
+
+/// Behaviours of the sequence trigger once it reaches the last step.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceMode {
+    Stop = 0,
+    Loop = 1,
+    Last = 2,
+}
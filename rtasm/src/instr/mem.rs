@@ -17,7 +17,7 @@ use crate::core::{
 };
 
 pub fn legacy_malloc_inner(args: HandlerArgs, float_mem: bool) -> HandlerData {
-    let (mem_x, mem_y) = (45.0, 165.0 + args.routine_count as f64 * 30.0);
+    let (mem_x, mem_y) = (45.0, 165.0 + args.routines_height);
     let mem_size = args.args[0].to_int().unwrap() as i16;
 
     let start_counter_id = args.ptrpos_id - mem_size - 1;
@@ -218,7 +218,7 @@ pub fn legacy_fmalloc(args: HandlerArgs) -> HandlerReturn {
 }
 
 pub fn init_mem(args: HandlerArgs) -> HandlerReturn {
-    let y_offset = args.routine_count as f64 * 30.0 + 150.0;
+    let y_offset = args.routines_height + 150.0;
     let mut cfg = GDObjConfig::new().pos(-15.0, 0.0).scale(0.25, 0.25);
 
     let mem_info = match args.mem_info {
@@ -230,7 +230,9 @@ pub fn init_mem(args: HandlerArgs) -> HandlerReturn {
                 routine: String::new(),
                 error: true,
                 line: args.line,
+                span: None,
                 details: "Cannot initialise memory when none exists.".into(),
+                help: None,
             });
         }
     };
@@ -323,7 +325,9 @@ pub fn legacy_mptr(args: HandlerArgs) -> HandlerReturn {
             routine: String::new(),
             error: true,
             line: args.line,
+            span: None,
             details: invalid_move_reason,
+            help: None,
         })
     }
 }
@@ -414,11 +418,13 @@ pub fn malloc_generator(args: HandlerArgs, float_mem: bool) -> HandlerReturn {
             routine: String::new(),
             error: true,
             line: args.line,
+            span: None,
             details: format!("Cannot allocate memory from {start_ctr} to {end_ctr}."),
+            help: None,
         });
     }
 
-    let y_offset = 45.0 + args.routine_count as f64 * 30.0;
+    let y_offset = 45.0 + args.routines_height;
 
     let ptrpos = end_ctr;
     let memreg = end_ctr - 1;
@@ -9,13 +9,21 @@ use crate::{
     core::{
         HandlerFn,
         flags::FlagValue,
-        structs::{HandlerArgs, InstrType, TasmPrimitive, TasmValue, TasmValueType},
+        structs::{
+            HandlerArgs, InstrType, SpawnKind, TasmPrimitive, TasmValue, TasmValueType,
+            fits_arg_signature,
+        },
     },
-    instr::{fns::*, mem::*},
+    instr::{console::*, events::*, fns::*, framebuffer::*, mem::*, sound::*},
 };
 
+pub mod console;
+pub mod events;
 pub mod fns;
+pub mod framebuffer;
+pub mod ids;
 pub mod mem;
+pub mod sound;
 
 /// Length of 1 game tick in seconds.
 pub const GROUP_SPAWN_DELAY: f64 = 0.0044;
@@ -32,6 +40,29 @@ macro_rules! argset {
     }
 }
 
+// spawn instructions, by how many of their routines they spawn and how many ticks it takes.
+// everything but instant instructions and SPAWN goes through an auxiliary spawn trigger
+const SPAWNS: InstrType = InstrType::Spawn {
+    kind: SpawnKind::Always,
+    delay: 1,
+};
+const MAYBE_SPAWNS: InstrType = InstrType::Spawn {
+    kind: SpawnKind::Maybe,
+    delay: 2,
+};
+const INSTANT_MAYBE_SPAWNS: InstrType = InstrType::Spawn {
+    kind: SpawnKind::Maybe,
+    delay: 1,
+};
+const SPAWNS_ONE_OF: InstrType = InstrType::Spawn {
+    kind: SpawnKind::OneOf,
+    delay: 2,
+};
+const INSTANT_SPAWNS_ONE_OF: InstrType = InstrType::Spawn {
+    kind: SpawnKind::OneOf,
+    delay: 1,
+};
+
 pub type HandlerAssoc = (&'static [TasmValueType], HandlerFn);
 pub type Handlers = &'static [HandlerAssoc];
 pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_map! {
@@ -64,11 +95,40 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
         &[argset!((Item) => display)],
         InstrType::Init,
     ),
+    "COLBLOCK" => (
+        true,
+        &[
+            argset!((Int) => colblock),
+            argset!((Int, Group) => colblock),
+        ],
+        InstrType::Init,
+    ),
+    "CONSOLE" => (
+        true,
+        &[
+            argset!((Int, Int) => console),
+            argset!((Int, Int, Int) => console),
+        ],
+        InstrType::Init,
+    ),
+    "FRAMEBUFFER" => (
+        true,
+        &[
+            argset!((Int, Int, Int, Int) => framebuffer),
+            argset!((Int, Int, Int, Int, Int) => framebuffer),
+        ],
+        InstrType::Init,
+    ),
     "IOBLOCK" => (
         true,
         &[argset!((Group, Int, String) => ioblock)],
         InstrType::Init,
     ),
+    "INPUT" => (
+        true,
+        &[argset!((Item, String, Group) => input)],
+        InstrType::Init,
+    ),
     // legacy memory
     "LMALLOC" => (
         true,
@@ -119,14 +179,14 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
     // debug
     "BREAKPOINT" => (
         false,
-        &[argset!(() => skip)],
+        &[argset!(() => breakpoint)],
         InstrType::Debug,
     ),
     // Process
     "SPAWN" => (
         false,
         &[argset!((Group) => spawn)],
-        InstrType::Process,
+        SPAWNS,
     ),
     // Waits
     "NOP" => (false, &[argset!(() => nop)], InstrType::Wait),
@@ -223,7 +283,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => spawn_item_item_eq),
             argset!((Group, Item, Number) => spawn_item_num_eq),
         ],
-        InstrType::Process,
+        MAYBE_SPAWNS,
     ),
     "SNE" => (
         false,
@@ -231,7 +291,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => spawn_item_item_ne),
             argset!((Group, Item, Number) => spawn_item_num_ne),
         ],
-        InstrType::Process,
+        MAYBE_SPAWNS,
     ),
     "SL" => (
         false,
@@ -239,7 +299,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => spawn_item_item_le),
             argset!((Group, Item, Number) => spawn_item_num_le),
         ],
-        InstrType::Process,
+        MAYBE_SPAWNS,
     ),
     "SLE" => (
         false,
@@ -247,7 +307,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => spawn_item_item_leq),
             argset!((Group, Item, Number) => spawn_item_num_leq),
         ],
-        InstrType::Process,
+        MAYBE_SPAWNS,
     ),
     "SG" => (
         false,
@@ -255,7 +315,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => spawn_item_item_ge),
             argset!((Group, Item, Number) => spawn_item_num_ge),
         ],
-        InstrType::Process,
+        MAYBE_SPAWNS,
     ),
     "SGE" => (
         false,
@@ -263,7 +323,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => spawn_item_item_geq),
             argset!((Group, Item, Number) => spawn_item_num_geq),
         ],
-        InstrType::Process,
+        MAYBE_SPAWNS,
     ),
     "FE" => (
         false,
@@ -271,7 +331,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => fork_item_item_eq),
             argset!((Group, Group, Item, Number) => fork_item_num_eq),
         ],
-        InstrType::Process,
+        SPAWNS_ONE_OF,
     ),
     "FNE" => (
         false,
@@ -279,7 +339,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => fork_item_item_ne),
             argset!((Group, Group, Item, Number) => fork_item_num_ne),
         ],
-        InstrType::Process,
+        SPAWNS_ONE_OF,
     ),
     "FL" => (
         false,
@@ -287,7 +347,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => fork_item_item_le),
             argset!((Group, Group, Item, Number) => fork_item_num_le),
         ],
-        InstrType::Process,
+        SPAWNS_ONE_OF,
     ),
     "FLE" => (
         false,
@@ -295,7 +355,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => fork_item_item_leq),
             argset!((Group, Group, Item, Number) => fork_item_num_leq),
         ],
-        InstrType::Process,
+        SPAWNS_ONE_OF,
     ),
     "FG" => (
         false,
@@ -303,7 +363,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => fork_item_item_ge),
             argset!((Group, Group, Item, Number) => fork_item_num_ge),
         ],
-        InstrType::Process,
+        SPAWNS_ONE_OF,
     ),
     "FGE" => (
         false,
@@ -311,16 +371,34 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => fork_item_item_geq),
             argset!((Group, Group, Item, Number) => fork_item_num_geq),
         ],
-        InstrType::Process,
+        SPAWNS_ONE_OF,
     ),
     "SRAND" => (
         false,
         &[argset!((Group, Number) => spawn_random)],
-        InstrType::Process,
+        MAYBE_SPAWNS,
     ),
     "FRAND" => (
         false,
         &[argset!((Group, Group, Number) => fork_random)],
+        SPAWNS_ONE_OF,
+    ),
+    "SRANDW" => (
+        false,
+        &[argset!((Dict) => spawn_random_weighted)],
+        SPAWNS_ONE_OF,
+    ),
+    "SEQUENCE" => (
+        false,
+        &[
+            argset!((Dict) => sequence),
+            argset!((Dict, String) => sequence),
+        ],
+        SPAWNS_ONE_OF,
+    ),
+    "SEQRESET" => (
+        false,
+        &[argset!((Group) => sequence_reset)],
         InstrType::Process,
     ),
     "ISE" => (
@@ -329,7 +407,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => instant_spawn_item_item_eq),
             argset!((Group, Item, Number) => instant_spawn_item_num_eq),
         ],
-        InstrType::Process,
+        INSTANT_MAYBE_SPAWNS,
     ),
     "ISNE" => (
         false,
@@ -337,7 +415,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => instant_spawn_item_item_ne),
             argset!((Group, Item, Number) => instant_spawn_item_num_ne),
         ],
-        InstrType::Process,
+        INSTANT_MAYBE_SPAWNS,
     ),
     "ISL" => (
         false,
@@ -345,7 +423,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => instant_spawn_item_item_le),
             argset!((Group, Item, Number) => instant_spawn_item_num_le),
         ],
-        InstrType::Process,
+        INSTANT_MAYBE_SPAWNS,
     ),
     "ISLE" => (
         false,
@@ -353,7 +431,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => instant_spawn_item_item_leq),
             argset!((Group, Item, Number) => instant_spawn_item_num_leq),
         ],
-        InstrType::Process,
+        INSTANT_MAYBE_SPAWNS,
     ),
     "ISG" => (
         false,
@@ -361,7 +439,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => instant_spawn_item_item_ge),
             argset!((Group, Item, Number) => instant_spawn_item_num_ge),
         ],
-        InstrType::Process,
+        INSTANT_MAYBE_SPAWNS,
     ),
     "ISGE" => (
         false,
@@ -369,7 +447,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Item, Item) => instant_spawn_item_item_geq),
             argset!((Group, Item, Number) => instant_spawn_item_num_geq),
         ],
-        InstrType::Process,
+        INSTANT_MAYBE_SPAWNS,
     ),
     "IFE" => (
         false,
@@ -377,7 +455,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => instant_fork_item_item_eq),
             argset!((Group, Group, Item, Number) => instant_fork_item_num_eq),
         ],
-        InstrType::Process,
+        INSTANT_SPAWNS_ONE_OF,
     ),
     "IFNE" => (
         false,
@@ -385,7 +463,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => instant_fork_item_item_ne),
             argset!((Group, Group, Item, Number) => instant_fork_item_num_ne),
         ],
-        InstrType::Process,
+        INSTANT_SPAWNS_ONE_OF,
     ),
     "IFL" => (
         false,
@@ -393,7 +471,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => instant_fork_item_item_le),
             argset!((Group, Group, Item, Number) => instant_fork_item_num_le),
         ],
-        InstrType::Process,
+        INSTANT_SPAWNS_ONE_OF,
     ),
     "IFLE" => (
         false,
@@ -401,7 +479,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => instant_fork_item_item_leq),
             argset!((Group, Group, Item, Number) => instant_fork_item_num_leq),
         ],
-        InstrType::Process,
+        INSTANT_SPAWNS_ONE_OF,
     ),
     "IFG" => (
         false,
@@ -409,7 +487,7 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => instant_fork_item_item_ge),
             argset!((Group, Group, Item, Number) => instant_fork_item_num_ge),
         ],
-        InstrType::Process,
+        INSTANT_SPAWNS_ONE_OF,
     ),
     "IFGE" => (
         false,
@@ -417,17 +495,22 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
             argset!((Group, Group, Item, Item) => instant_fork_item_item_geq),
             argset!((Group, Group, Item, Number) => instant_fork_item_num_geq),
         ],
-        InstrType::Process,
+        INSTANT_SPAWNS_ONE_OF,
     ),
     "ISRAND" => (
         false,
         &[argset!((Group, Number) => instant_spawn_random)],
-        InstrType::Process,
+        INSTANT_MAYBE_SPAWNS,
+    ),
+    "ISRANDW" => (
+        false,
+        &[argset!((Dict) => instant_spawn_random_weighted)],
+        INSTANT_SPAWNS_ONE_OF,
     ),
     "IFRAND" => (
         false,
         &[argset!((Group, Group, Number) => instant_fork_random)],
-        InstrType::Process,
+        INSTANT_SPAWNS_ONE_OF,
     ),
     "TSPAWN" => (
         false,
@@ -469,6 +552,85 @@ pub const INSTR_SPEC: phf::Map<&'static str, (bool, Handlers, InstrType)> = phf_
         &[argset!((Group) => toff)],
         InstrType::Process,
     ),
+    // Events
+    // listens on the counter, and spawns the routine when it reaches the value.
+    "ONCOUNT" => (
+        false,
+        &[argset!((Item, Int, Group) => oncount)],
+        InstrType::Event,
+    ),
+    // collision between two blocks, a block and a player, or both players
+    "ONCOLLIDE" => (
+        false,
+        &[
+            argset!((Int, Int, Group) => oncollide),
+            argset!((Int, Group) => oncollide),
+            argset!((Group) => oncollide),
+        ],
+        InstrType::Event,
+    ),
+    "ONEXIT" => (
+        false,
+        &[
+            argset!((Int, Int, Group) => onexit),
+            argset!((Int, Group) => onexit),
+            argset!((Group) => onexit),
+        ],
+        InstrType::Event,
+    ),
+    // game events, such as jumps or orbs, by name
+    "ONEVENT" => (
+        false,
+        &[argset!((String, Group) => onevent)],
+        InstrType::Event,
+    ),
+    // Console
+    "PRINT" => (
+        false,
+        &[
+            argset!((Item) => console_print),
+            argset!((Number) => console_print),
+        ],
+        InstrType::Console,
+    ),
+    "PRINTLN" => (false, &[argset!(() => console_newline)], InstrType::Console),
+    "CLS" => (false, &[argset!(() => console_clear)], InstrType::Console),
+    // Framebuffer
+    "PLOT" => (
+        false,
+        &[
+            argset!((Item, Item) => plot),
+            argset!((Item, Item, String) => plot),
+            argset!((Number, Number) => plot),
+            argset!((Number, Number, String) => plot),
+        ],
+        InstrType::Framebuffer,
+    ),
+    "CLEAR" => (false, &[argset!(() => clear)], InstrType::Framebuffer),
+    // Sound
+    "SFX" => (
+        false,
+        &[argset!((Int, Number, Number) => sfx)],
+        InstrType::Special,
+    ),
+    "SFXSTOP" => (
+        false,
+        &[argset!((Int) => sfx_stop)],
+        InstrType::Special,
+    ),
+    "SONG" => (
+        false,
+        &[argset!((Int, Number) => song)],
+        InstrType::Special,
+    ),
+    "SONGSTOP" => (
+        false,
+        &[
+            argset!(() => song_stop),
+            argset!((Int) => song_stop),
+        ],
+        InstrType::Special,
+    ),
     "RAW" => (
         false,
         &[argset!((String) => raw_objs)],
@@ -492,6 +654,16 @@ pub fn get_item_spec(item: &TasmValue) -> Option<Item> {
     }
 }
 
+/// Returns the handler of the instruction for the given arguments, if it has one.
+pub fn find_handler(ident: &str, args: &[TasmValue]) -> Option<HandlerFn> {
+    INSTR_SPEC
+        .get(ident)?
+        .1
+        .iter()
+        .find(|&(sig, _)| fits_arg_signature(args, sig))
+        .map(|v| v.1)
+}
+
 /// Returns the value of the flag if it was given as a flag argument. Returns the `default` value if it was not passed.
 fn get_flag_value(args: &HandlerArgs, ident: &str, default: FlagValue) -> FlagValue {
     match args.flag_by_ident.get(ident) {
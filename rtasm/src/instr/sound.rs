@@ -0,0 +1,113 @@
+use gdlib::gdobj::{
+    GDObject, GDValue,
+    ids::{
+        objects::TRIGGER_SONG,
+        properties::{
+            FADE_IN_TIME_IN_MS, FADE_OUT_TIME_IN_MS, LOOP_SONG, SONG_CHANNEL, SONG_ID, SONG_VOLUME,
+        },
+    },
+};
+
+use crate::{
+    core::{
+        HandlerReturn,
+        flags::FlagValue,
+        structs::{HandlerArgs, HandlerData},
+    },
+    instr::{
+        get_flag_value, get_flag_value_opt,
+        ids::{
+            SFX_IS_UNIQUE, SFX_PITCH, SFX_UNIQUE_ID, SOUND_STOP, TRIGGER_EDIT_SFX,
+            TRIGGER_EDIT_SONG, TRIGGER_SFX,
+        },
+    },
+};
+
+/// Returns the fade in and fade out flags, converted from seconds to milliseconds.
+fn fade_props(args: &HandlerArgs) -> [(u16, GDValue); 2] {
+    let to_ms = |ident| {
+        let secs: f64 = get_flag_value(args, ident, FlagValue::Float(0.0)).into();
+        GDValue::Int((secs * 1000.0) as i32)
+    };
+    [
+        (FADE_IN_TIME_IN_MS, to_ms("fadein")),
+        (FADE_OUT_TIME_IN_MS, to_ms("fadeout")),
+    ]
+}
+
+pub fn sfx(args: HandlerArgs) -> HandlerReturn {
+    let mut props = vec![
+        (SONG_ID, GDValue::Int(args.args[0].to_int().unwrap())),
+        (
+            SONG_VOLUME,
+            GDValue::Float(args.args[1].to_float().unwrap()),
+        ),
+        (SFX_PITCH, GDValue::Float(args.args[2].to_float().unwrap())),
+        (
+            LOOP_SONG,
+            GDValue::Bool(get_flag_value(&args, "loop", FlagValue::Bool(false)).into()),
+        ),
+    ];
+    props.extend(fade_props(&args));
+
+    // a unique ID is needed to stop the sound effect later
+    if let Some(uid) = get_flag_value_opt(&args, "uid") {
+        props.push((SFX_IS_UNIQUE, GDValue::Bool(true)));
+        props.push((SFX_UNIQUE_ID, GDValue::Int(uid.into())));
+    }
+
+    Ok(HandlerData::from_objects(vec![GDObject::new(
+        TRIGGER_SFX,
+        &args.cfg,
+        props,
+    )]))
+}
+
+pub fn sfx_stop(args: HandlerArgs) -> HandlerReturn {
+    Ok(HandlerData::from_objects(vec![GDObject::new(
+        TRIGGER_EDIT_SFX,
+        &args.cfg,
+        vec![
+            (SFX_UNIQUE_ID, GDValue::Int(args.args[0].to_int().unwrap())),
+            (SOUND_STOP, GDValue::Bool(true)),
+        ],
+    )]))
+}
+
+pub fn song(args: HandlerArgs) -> HandlerReturn {
+    let mut props = vec![
+        (SONG_ID, GDValue::Int(args.args[0].to_int().unwrap())),
+        (
+            SONG_VOLUME,
+            GDValue::Float(args.args[1].to_float().unwrap()),
+        ),
+        (
+            SONG_CHANNEL,
+            GDValue::Int(get_flag_value(&args, "channel", FlagValue::Int(0)).into()),
+        ),
+        (
+            LOOP_SONG,
+            GDValue::Bool(get_flag_value(&args, "loop", FlagValue::Bool(false)).into()),
+        ),
+    ];
+    props.extend(fade_props(&args));
+
+    Ok(HandlerData::from_objects(vec![GDObject::new(
+        TRIGGER_SONG,
+        &args.cfg,
+        props,
+    )]))
+}
+
+pub fn song_stop(args: HandlerArgs) -> HandlerReturn {
+    // stops channel 0 if none is given
+    let channel = args.args.first().and_then(|c| c.to_int()).unwrap_or(0);
+    Ok(HandlerData::from_objects(vec![GDObject::new(
+        TRIGGER_EDIT_SONG,
+        &args.cfg,
+        vec![
+            (SONG_CHANNEL, GDValue::Int(channel)),
+            (SOUND_STOP, GDValue::Bool(true)),
+        ],
+    )]))
+}
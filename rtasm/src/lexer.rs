@@ -39,20 +39,47 @@
 use crate::{
     core::{
         consts::{ENTRY_POINT, INIT_ROUTINE},
-        error::{ParseErrorType, TasmError, TasmErrorType},
-        flags::{Flag, FlagValueType, get_flag_type},
+        error::{NO_ROUTINE, ParseErrorType, Span, TasmError, TasmErrorType},
+        flags::{Flag, FlagValue, FlagValueType, flag_idents, get_flag_type, parse_dict},
+        lint::{Lint, parse_pragma},
         push_error, push_error_lineless,
         structs::{
-            Instruction, Routine, RoutineData, Tasm, TasmValue, fits_arg_signature,
-            is_builtin_alias,
+            InstrType, Instruction, Routine, RoutineData, Tasm, TasmValue, fits_arg_signature,
+            is_builtin_alias, mismatched_arg, signature_str,
         },
+        suggest::did_you_mean,
     },
-    instr::INSTR_SPEC,
+    instr::{INSTR_SPEC, events::EVENT_NAMES, get_item_spec},
     verbose_log,
 };
 use std::collections::{HashMap, hash_map};
 
 const INIT_PLACEHOLDER_GROUP: i16 = -1i16;
+/// Marks a routine that is used outside of the file, e.g. `@export utility:`
+const EXPORT_ATTRIBUTE: &str = "@export ";
+
+/// Line of the source file that errors can point into.
+struct SourceLine<'a> {
+    /// Line without comments, with tabs converted to spaces
+    source: &'a str,
+    /// Line without indentation, which every part that is pointed at is a slice of
+    trimmed: &'a str,
+}
+
+impl SourceLine<'_> {
+    /// Span of `part`, which must be a slice of the trimmed line.
+    fn span(&self, part: &str) -> Span {
+        self.span_between(part, part)
+    }
+
+    /// Span from the start of `first` to the end of `last`, which must be slices of the trimmed line.
+    fn span_between(&self, first: &str, last: &str) -> Span {
+        let indent = self.source.len() - self.source.trim_start().len();
+        let offset =
+            |part: &str| indent + (part.as_ptr() as usize - self.trimmed.as_ptr() as usize);
+        Span::new(self.source, offset(first), offset(last) + last.len())
+    }
+}
 
 impl Tasm {
     pub fn parse(&mut self, group_offset: i16, disable_entry_point_check: bool) {
@@ -92,11 +119,15 @@ impl Tasm {
                 TasmErrorType::NoEntryPoint,
                 "No entry point found in file.".into(),
             );
+            self.suggest_fix(
+                "Add a _start routine, or pass --no-entry-point if the file is a library.",
+            );
         }
 
         verbose_log!(self, "Parsing instructions.");
         self.handle_instructions();
 
+        self.parse_warnings = self.warnings.len();
         if !self.errors.is_empty() {
             verbose_log!(self, "Parsed file with {} errors.", self.errors.len());
         } else {
@@ -116,6 +147,7 @@ impl Tasm {
             if !raw_instr.to_uppercase().starts_with("ALIAS ") {
                 continue;
             }
+            let (first_error, first_warning) = (self.errors.len(), self.warnings.len());
             let args = raw_instr.split('|').next().unwrap();
             let trimmed = &args[6..] // condition above ensures that this never fails
                 .split(',')
@@ -158,8 +190,22 @@ impl Tasm {
                                 format!("Cannot override default alias {}.", entry.key()),
                             );
                         } else {
+                            let used = self.lines.iter().enumerate().any(|(idx, l)| {
+                                idx != *line && tokens(l).any(|t| t == entry.key())
+                            });
+                            if !used {
+                                self.warnings.push(TasmError {
+                                    etype: TasmErrorType::UnusedAlias,
+                                    file: self.fname.clone(),
+                                    routine: INIT_ROUTINE.into(),
+                                    error: false,
+                                    line: *line,
+                                    span: None,
+                                    details: format!("Alias {} is never used.", entry.key()),
+                                    help: Some("Remove the alias.".into()),
+                                });
+                            }
                             entry.insert(trimmed[1].into());
-                            continue;
                         }
                     }
                 };
@@ -173,6 +219,17 @@ impl Tasm {
                     format!("Bad alias identifier: {}", trimmed[0]),
                 );
             };
+
+            let source = self.lines[*line].clone();
+            let source_line = SourceLine {
+                source: &source,
+                trimmed: raw_instr,
+            };
+            let span = source_line.span(trimmed[0]);
+            self.point_errors_at(first_error, &span);
+            for w in &mut self.warnings[first_warning..] {
+                w.span = Some(Box::new(span.clone()));
+            }
         }
 
         // put these back after taking
@@ -199,7 +256,8 @@ impl Tasm {
                             INIT_PLACEHOLDER_GROUP => 0,
                             g => g,
                         })
-                        .ident(&r.routine_ident), // routine object
+                        .ident(&r.routine_ident)
+                        .exported(r.exported), // routine object
                 )
             })
             .collect();
@@ -228,7 +286,47 @@ impl Tasm {
         }
     }
 
+    /// Suggests a fix for the last error.
+    fn suggest_fix<T: Into<String>>(&mut self, help: T) {
+        if let Some(e) = self.errors.last_mut() {
+            e.help = Some(help.into());
+        }
+    }
+
+    /// Points every error since `first_error` that does not point anywhere yet at the given span.
+    fn point_errors_at(&mut self, first_error: usize, span: &Span) {
+        for e in &mut self.errors[first_error..] {
+            e.span.get_or_insert_with(|| Box::new(span.clone()));
+        }
+    }
+
     fn parse_raw_value(&mut self, v: &str, curr_line: usize, routine: &str) -> Option<TasmValue> {
+        // dicts and lists need the routine and alias maps to be resolved, so they are parsed here
+        if v.trim().starts_with(['{', '[']) {
+            return match parse_dict(v.trim(), &self.routine_group_map, &self.defined_aliases) {
+                Some(d) => Some(TasmValue::Dict(d)),
+                None => {
+                    push_error(
+                        &mut self.errors,
+                        &self.fname,
+                        TasmErrorType::InvalidArguments,
+                        curr_line,
+                        routine.to_string(),
+                        format!(
+                            "Unable to parse {} {}",
+                            if v.trim().starts_with('[') {
+                                "list"
+                            } else {
+                                "dict"
+                            },
+                            v.trim()
+                        ),
+                    );
+                    None
+                }
+            };
+        }
+
         match TasmValue::to_value(v.trim()) {
             // whitespace is stripped when parsing
             Ok(t) => self.parse_tasm_value(t, curr_line, routine),
@@ -263,6 +361,16 @@ impl Tasm {
         // determine the arguments and the flags
         // line is structured like this:
         // <whitespace> INSTR [...ARGS] [| ...FLAGS]
+        let source = self.lines[curr_line].clone();
+        let line = SourceLine {
+            source: &source,
+            trimmed: trimmed_line,
+        };
+        // everything after the first |, which is empty at the end of the line if there are no flags
+        let flags_section = match trimmed_line.find('|') {
+            Some(pos) => &trimmed_line[pos + 1..],
+            None => &trimmed_line[trimmed_line.len()..],
+        };
 
         let (args_string, flags) = match split_at_char_once(
             trimmed_line,
@@ -273,7 +381,9 @@ impl Tasm {
                 routine: curr_routine.ident.clone(),
                 error: true,
                 line: curr_line,
+                span: Some(Box::new(line.span(flags_section))),
                 details: "Bad flag arguments".into(),
+                help: None,
             },
         ) {
             Ok((left, right)) => {
@@ -282,6 +392,7 @@ impl Tasm {
                 } else {
                     let flags_parsed = match parse_flags_str(
                         right,
+                        &line,
                         curr_line,
                         &self.fname,
                         &curr_routine.ident,
@@ -306,11 +417,18 @@ impl Tasm {
 
         let instr: String;
         let mut args: Vec<TasmValue> = vec![];
+        // parts of the line that each argument was parsed from
+        let mut arg_spans: Vec<Span> = vec![];
 
         let is_concurrent: bool;
+        let mnemonic: &str;
+        // every argument, or the mnemonic if there are none
+        let args_span: Span;
 
         if let Some(pos) = args_string.trim().find(" ") {
-            if args_string.ends_with(',') {
+            mnemonic = &args_string[..pos];
+            args_span = line.span(args_string[pos + 1..].trim());
+            if let Some(before_comma) = args_string.strip_suffix(',') {
                 push_error(
                     &mut self.errors,
                     &self.fname,
@@ -319,10 +437,13 @@ impl Tasm {
                     curr_routine.ident.clone(),
                     "Trailing commas are not allowed.".to_string(),
                 );
+                let comma = &args_string[before_comma.len()..];
+                self.point_errors_at(self.errors.len() - 1, &line.span(comma));
+                self.suggest_fix("Remove the trailing comma.");
                 return;
             }
 
-            let instr_raw = args_string[..pos].to_uppercase();
+            let instr_raw = mnemonic.to_uppercase();
             if let Some(stripped) = instr_raw.strip_prefix('~') {
                 is_concurrent = true;
                 instr = stripped.to_string();
@@ -342,28 +463,49 @@ impl Tasm {
                         curr_routine.ident.clone(),
                         "Cannot define an alias outside of the init routine.".to_string(),
                     );
+                    self.point_errors_at(self.errors.len() - 1, &line.span(mnemonic));
+                    self.suggest_fix("Move the alias into the _init routine.");
                 }
                 return;
             }
 
             let mut erroneous_instr = false;
             // get all chars after the first space, which separates the instruction and args
-            let mut raw_args = args_string[pos + 1..]
-                .split(',')
-                .map(|v| v.trim().to_string())
-                .collect::<Vec<_>>();
-
-            for raw in raw_args.iter_mut() {
+            for raw in split_args(&args_string[pos + 1..]) {
                 // replace if an alias is referenced
-                if let Some(raw_val) = self.defined_aliases.get(&raw.to_string()) {
-                    *raw = raw_val.clone();
+                let value = match self.defined_aliases.get(raw) {
+                    Some(raw_val) => raw_val.clone(),
+                    None => {
+                        self.check_shadowed_item(raw, curr_line, &curr_routine.ident, &line);
+                        raw.to_string()
+                    }
+                };
+                if let Some(&empty_line) = self.empty_routines.get(&value) {
+                    self.warnings.push(TasmError {
+                        etype: TasmErrorType::EmptyRoutineSpawn,
+                        file: self.fname.clone(),
+                        routine: curr_routine.ident.clone(),
+                        error: false,
+                        line: curr_line,
+                        span: Some(Box::new(line.span(raw))),
+                        details: format!(
+                            "Routine {value} on line {} has no instructions, so its group belongs to the routine after it.",
+                            empty_line + 1
+                        ),
+                        help: Some(format!("Add instructions to {value}, or stop spawning it.")),
+                    });
                 }
-            }
 
-            for raw in raw_args {
-                match self.parse_raw_value(&raw, curr_line, &curr_routine.ident) {
-                    Some(v) => args.push(v),
-                    None => erroneous_instr = true,
+                let first_error = self.errors.len();
+                match self.parse_raw_value(&value, curr_line, &curr_routine.ident) {
+                    Some(v) => {
+                        args.push(v);
+                        arg_spans.push(line.span(raw));
+                    }
+                    None => {
+                        erroneous_instr = true;
+                        self.point_errors_at(first_error, &line.span(raw));
+                    }
                 }
             }
             if erroneous_instr {
@@ -376,10 +518,13 @@ impl Tasm {
                     curr_routine.ident.clone(),
                     "Failed to parse instruction: invalid argset".into(),
                 );
+                self.point_errors_at(self.errors.len() - 1, &args_span);
             }
         } else {
             // no args or extras (everything after | )
-            let instr_raw = args_string.to_uppercase();
+            mnemonic = args_string.trim();
+            args_span = line.span(mnemonic);
+            let instr_raw = mnemonic.to_uppercase();
             if let Some(stripped) = instr_raw.strip_prefix('~') {
                 is_concurrent = true;
                 instr = stripped.to_string();
@@ -401,6 +546,10 @@ impl Tasm {
                     curr_routine.ident.clone(),
                     format!("Unrecognized instruction {instr}"),
                 );
+                self.point_errors_at(self.errors.len() - 1, &line.span(mnemonic));
+                if let Some(help) = did_you_mean(&instr, INSTR_SPEC.keys().copied()) {
+                    self.suggest_fix(help);
+                }
                 return;
             }
         };
@@ -418,6 +567,33 @@ impl Tasm {
                     curr_routine.ident
                 ),
             );
+            self.point_errors_at(self.errors.len() - 1, &line.span(mnemonic));
+            return;
+        }
+
+        // comparison operators are only meaningful for event instructions,
+        // everything else expects an arithmetic operator
+        if *itype != InstrType::Event
+            && let Some(flag) = flags
+                .iter()
+                .find(|f| matches!(f.value, FlagValue::Compare(_)))
+        {
+            push_error(
+                &mut self.errors,
+                &self.fname,
+                TasmErrorType::BadFlag,
+                curr_line,
+                curr_routine.ident.clone(),
+                format!(
+                    "Flag {} of instruction {instr} expects an arithmetic operator, not a comparison.",
+                    flag.ident
+                ),
+            );
+            let segment = flags_section
+                .split_whitespace()
+                .find(|f| f.split(':').next() == Some(flag.ident.as_str()))
+                .unwrap_or(flags_section.trim());
+            self.point_errors_at(self.errors.len() - 1, &line.span(segment));
             return;
         }
 
@@ -428,6 +604,29 @@ impl Tasm {
             .map(|v| v.1)
         {
             Some(handler) => {
+                // event names are the only strings that event argsets take
+                if *itype == InstrType::Event
+                    && let Some((name, span)) = args
+                        .iter()
+                        .zip(&arg_spans)
+                        .filter_map(|(a, span)| Some((a.to_string()?, span)))
+                        .find(|(name, _)| !EVENT_NAMES.contains_key(name.as_str()))
+                {
+                    push_error(
+                        &mut self.errors,
+                        &self.fname,
+                        TasmErrorType::UnknownEvent,
+                        curr_line,
+                        curr_routine.ident.clone(),
+                        format!("Unknown event {name} in instruction {instr}."),
+                    );
+                    self.point_errors_at(self.errors.len() - 1, span);
+                    if let Some(help) = did_you_mean(&name, EVENT_NAMES.keys().copied()) {
+                        self.suggest_fix(help);
+                    }
+                    return;
+                }
+
                 // finally, add instruction to routine
                 curr_routine.add_instruction(Instruction {
                     ident: instr.clone(),
@@ -452,6 +651,137 @@ impl Tasm {
                         "Instruction {instr} has no argument handler for the argset {argtypes:?}"
                     ),
                 );
+                // point at a misspelled name, or else at the first argument that fits no argset
+                let (help, suggested) = self.unknown_names_help(&args).unzip();
+                let offending = suggested
+                    .or_else(|| mismatched_arg(&args, handlers.iter().map(|(sig, _)| *sig)));
+                let span = offending.map_or(&args_span, |idx| &arg_spans[idx]);
+                self.point_errors_at(self.errors.len() - 1, span);
+                let argsets = handlers
+                    .iter()
+                    .map(|(sig, _)| signature_str(sig))
+                    .collect::<Vec<_>>();
+                let argsets = match argsets.len() {
+                    1 => format!("{instr} takes {}.", argsets[0]),
+                    _ => format!("{instr} takes one of: {}.", argsets.join("; ")),
+                };
+                self.suggest_fix(
+                    help.into_iter()
+                        .chain([argsets])
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+            }
+        }
+    }
+
+    /// Suggestions for arguments that look like misspelled routines or aliases,
+    /// along with the index of the first of these arguments.
+    fn unknown_names_help(&self, args: &[TasmValue]) -> Option<(String, usize)> {
+        let names = self
+            .routine_group_map
+            .keys()
+            // the implicit routine before the first label has no name
+            .filter(|r| !r.is_empty() && r.as_str() != INIT_ROUTINE)
+            .chain(self.defined_aliases.keys())
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let suggestions = args
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, a)| match a {
+                TasmValue::String(s) => Some((
+                    idx,
+                    format!(
+                        "{s} is not a routine or alias. {}",
+                        did_you_mean(s, names.iter().copied())?
+                    ),
+                )),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let &(first, _) = suggestions.first()?;
+        let help = suggestions.into_iter().map(|(_, s)| s).collect::<Vec<_>>();
+        Some((help.join(" "), first))
+    }
+
+    /// Warns about items that are used directly, even though an alias refers to them.
+    fn check_shadowed_item(
+        &mut self,
+        raw: &str,
+        curr_line: usize,
+        routine: &str,
+        line: &SourceLine,
+    ) {
+        let Some(item) = TasmValue::to_value(raw)
+            .ok()
+            .as_ref()
+            .and_then(get_item_spec)
+        else {
+            return;
+        };
+        let Some(alias) = self.defined_aliases.iter().find_map(|(alias, value)| {
+            let aliased = TasmValue::to_value(value).ok();
+            (aliased.as_ref().and_then(get_item_spec) == Some(item)).then_some(alias)
+        }) else {
+            return;
+        };
+        self.warnings.push(TasmError {
+            etype: TasmErrorType::ShadowedCounter,
+            file: self.fname.clone(),
+            routine: routine.into(),
+            error: false,
+            line: curr_line,
+            span: Some(Box::new(line.span(raw))),
+            details: format!("{raw} is used directly, but it is aliased as {alias}."),
+            help: Some(format!("Use {alias} instead.")),
+        });
+    }
+
+    /// Reads `; tasm: allow(...)` pragmas from the comments of the source.
+    /// A pragma after an instruction applies to its line, and a pragma on a line of its own
+    /// applies to the line after it, or to the whole routine if that line declares a routine.
+    fn collect_pragmas(&mut self, source: &str) {
+        let mut pending: Vec<Lint> = vec![];
+        let mut routine = String::new();
+        for (idx, raw_line) in source.lines().enumerate() {
+            let (code, comment) = raw_line.split_once(';').unwrap_or((raw_line, ""));
+            let is_label = !code.trim().is_empty() && !code.starts_with([' ', '\t']);
+            if is_label {
+                let label = code.trim();
+                let label = label.strip_prefix(EXPORT_ATTRIBUTE).unwrap_or(label);
+                routine = label.trim().trim_end_matches(':').to_string();
+            }
+            let lints = match parse_pragma(comment) {
+                Some(Ok(lints)) => lints,
+                Some(Err(details)) => {
+                    self.warnings.push(TasmError {
+                        etype: TasmErrorType::BadPragma,
+                        file: self.fname.clone(),
+                        routine: routine.clone(),
+                        error: false,
+                        line: idx,
+                        span: None,
+                        details,
+                        help: None,
+                    });
+                    vec![]
+                }
+                None => vec![],
+            };
+
+            if code.trim().is_empty() {
+                pending.extend(lints);
+                continue;
+            }
+            pending.extend(lints);
+            let lints = core::mem::take(&mut pending);
+            if lints.is_empty() {
+                continue;
+            }
+            match is_label {
+                true => self.lints.allow_routine(&routine, &lints),
+                false => self.lints.allow_line(idx, &lints),
             }
         }
     }
@@ -501,10 +831,21 @@ impl Tasm {
                 } else {
                     // didn't commit empty routine, re-use its group
                     self.curr_group -= 1;
+                    if !routine_ident.is_empty() {
+                        self.empty_routines
+                            .insert(routine_ident, curr_routine_data.line_idx);
+                    }
                 }
 
                 // no indent, check for routine identifier.
                 let mut strip = line.trim().to_string();
+                let exported = match strip.strip_prefix(EXPORT_ATTRIBUTE) {
+                    Some(rest) => {
+                        strip = rest.trim_start().to_string();
+                        true
+                    }
+                    None => false,
+                };
                 if strip.ends_with(':') && !strip.contains(' ') {
                     self.curr_group += 1;
                     // now we are certain that this is a routine ident
@@ -521,18 +862,26 @@ impl Tasm {
                         .is_some()
                     {
                         verbose_log!(self, "Routine was already declared.");
-                        push_error(
-                            &mut self.errors,
-                            &self.fname,
-                            TasmErrorType::MultipleRoutineDefintions,
-                            line_idx,
-                            routine_ident.clone(),
-                            format!(
+                        // the identifier is the only part of the line besides the attribute and colon
+                        let start = line.rfind(&routine_ident).unwrap_or(0);
+                        self.errors.push(TasmError {
+                            etype: TasmErrorType::MultipleRoutineDefintions,
+                            file: self.fname.clone(),
+                            routine: routine_ident.clone(),
+                            error: true,
+                            line: line_idx,
+                            span: Some(Box::new(Span::new(
+                                line,
+                                start,
+                                start + routine_ident.len(),
+                            ))),
+                            details: format!(
                                 "Routine {} was already declared on line {}",
                                 routine_ident.clone(),
                                 seen_routines.get(&routine_ident).unwrap_or(&0)
                             ),
-                        );
+                            help: Some("Rename one of the routines.".into()),
+                        });
                     }
 
                     // clear out bad data
@@ -541,19 +890,22 @@ impl Tasm {
                         routine_ident,
                         group_id: self.curr_group,
                         lines: vec![],
+                        exported,
                     };
                     in_routine = true;
                 } else {
                     // this is not a routine identifier, so it is a bad token
                     verbose_log!(self, "Found bad token on line {line_idx}");
-                    push_error(
-                        &mut self.errors,
-                        &self.fname,
-                        TasmErrorType::BadToken,
-                        line_idx,
-                        "<No routine>".to_string(),
-                        "Bad token.".to_string(),
-                    );
+                    self.errors.push(TasmError {
+                        etype: TasmErrorType::BadToken,
+                        file: self.fname.clone(),
+                        routine: NO_ROUTINE.to_string(),
+                        error: true,
+                        line: line_idx,
+                        span: Some(Box::new(Span::new(line, 0, line.len()))),
+                        details: "Bad token.".to_string(),
+                        help: None,
+                    });
                 }
             } else if in_routine {
                 let trim = line.trim();
@@ -577,6 +929,12 @@ impl Tasm {
     }
 }
 
+/// Identifiers and literals of a line, which are separated by anything but letters, digits and underscores.
+fn tokens(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| !t.is_empty())
+}
+
 fn split_at_char_once(instr: &str, ch: char, err: TasmError) -> Result<(&str, &str), TasmError> {
     let mut line_split = instr.split(ch);
 
@@ -593,8 +951,29 @@ fn split_at_char_once(instr: &str, ch: char, err: TasmError) -> Result<(&str, &s
     Ok((left, right))
 }
 
+/// Splits an argset along each comma that is not inside of a dict or list.
+fn split_args(args: &str) -> Vec<&str> {
+    let mut raw_args = vec![];
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (i, c) in args.char_indices() {
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                raw_args.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    raw_args.push(args[start..].trim());
+    raw_args
+}
+
 fn parse_flags_str(
     flags_str: &str,
+    line: &SourceLine,
     curr_line: usize,
     file: &String,
     routine: &String,
@@ -607,6 +986,8 @@ fn parse_flags_str(
     let mut in_dict = false;
     let mut dict_ident = String::new();
     let mut current_dict = String::new();
+    // first segment of the current dict
+    let mut dict_start = "";
 
     // preprocessing, for joining of dicts
 
@@ -620,6 +1001,7 @@ fn parse_flags_str(
                     dict_ident.clone(),
                     current_dict.clone(),
                     FlagValueType::Dict,
+                    line.span_between(dict_start, flag_segment),
                 ));
             }
             continue;
@@ -634,7 +1016,9 @@ fn parse_flags_str(
                 routine: routine.to_owned(),
                 error: true,
                 line: curr_line,
+                span: Some(Box::new(line.span(flag_segment))),
                 details: format!("Bad flag: {flag_segment}"),
+                help: None,
             },
         ) {
             Ok((ident, value)) => match get_flag_type(ident) {
@@ -643,7 +1027,12 @@ fn parse_flags_str(
                         // if a dict flag is identified, it is the beginning of the dict
                         if value.ends_with('}') {
                             // dict is contained in one segment
-                            preprocessed.push((ident.into(), value.into(), FlagValueType::Dict));
+                            preprocessed.push((
+                                ident.into(),
+                                value.into(),
+                                FlagValueType::Dict,
+                                line.span(flag_segment),
+                            ));
                             continue;
                         }
 
@@ -654,8 +1043,11 @@ fn parse_flags_str(
                         in_dict = true;
                         dict_ident = ident.into();
                         current_dict = value.into();
+                        dict_start = flag_segment;
+                    }
+                    t => {
+                        preprocessed.push((ident.into(), value.into(), t, line.span(flag_segment)))
                     }
-                    t => preprocessed.push((ident.into(), value.into(), t)),
                 },
                 None => {
                     return Err(TasmError {
@@ -664,7 +1056,9 @@ fn parse_flags_str(
                         routine: routine.to_owned(),
                         error: true,
                         line: curr_line,
+                        span: Some(Box::new(line.span(flag_segment))),
                         details: format!("Unrecognized flag {flag_segment}"),
+                        help: did_you_mean(ident, flag_idents()),
                     });
                 }
             },
@@ -674,7 +1068,7 @@ fn parse_flags_str(
 
     let mut parsed_flags = vec![];
 
-    for (ident, raw_value, t) in preprocessed {
+    for (ident, raw_value, t, span) in preprocessed {
         match Flag::from(ident.clone(), &raw_value, t.clone(), gm, aliases) {
             Some(flag) => parsed_flags.push(flag),
             None => {
@@ -684,9 +1078,11 @@ fn parse_flags_str(
                     routine: routine.to_owned(),
                     error: true,
                     line: curr_line,
+                    span: Some(Box::new(span)),
                     details: format!(
                         "Unable to parse {ident} with value of {raw_value} and type {t:?}"
                     ),
+                    help: None,
                 });
             }
         }
@@ -717,7 +1113,9 @@ pub fn validate_tasm_value(
                         routine: routine.to_string(),
                         error: true,
                         line: curr_line,
+                        span: None,
                         details: "Cannot spawn init routine.".to_string(),
+                        help: Some("Move the instructions to spawn into another routine.".into()),
                     });
                     None
                 }
@@ -750,6 +1148,7 @@ pub fn parse_file<T: AsRef<str>>(
     tasm.logs_enabled = verbose_logs;
     tasm.group_offset = group_offset;
     tasm.fname = fname;
+    tasm.collect_pragmas(in_str.as_ref());
     tasm.parse(group_offset + 1, disable_entry_point_check);
 
     if tasm.errors.is_empty() {
@@ -2,7 +2,7 @@
 
 extern crate alloc;
 
-use std::{env, fs, path::PathBuf};
+use std::{env, fs, path::PathBuf, process::ExitCode};
 
 use anyhow::Error;
 use clap::Parser;
@@ -13,7 +13,15 @@ use gdlib::{
 };
 use tungstenite::{Message, connect};
 
-use crate::core::print_errors;
+use crate::core::{
+    diagnostics::{MessageFormat, print_json},
+    error::{TasmError, TasmErrorType},
+    layout::LayoutMode,
+    lint::LintLevel,
+    print_errors, print_warnings,
+    sourcemap::source_map_json,
+    timing::{analyse, timing_json, timing_table},
+};
 
 pub mod core;
 pub mod instr;
@@ -34,6 +42,58 @@ struct Args {
     #[arg(long, short)]
     release: bool,
 
+    /// Whether or not to use debug mode.
+    /// Debug mode makes BREAKPOINT pause every routine until the continue block is hit.
+    #[arg(long, short)]
+    debug: bool,
+
+    /// Instruments every routine with counters that track how often it was entered,
+    /// and which routine was entered last. Requires debug mode.
+    #[arg(long, requires = "debug")]
+    trace: bool,
+
+    /// Labels every instruction with its mnemonic and line number in the editor.
+    /// Not available in release mode, where instructions are too close together.
+    #[arg(long, conflicts_with = "release")]
+    annotate: bool,
+
+    /// How routines are placed in the level: linear, wrapped:<N> or grid:<N>.
+    /// Wrapped layouts split routines into rows of N instructions,
+    /// and grid layouts also pack these rows into columns.
+    #[arg(long, default_value = "linear", value_name = "LAYOUT")]
+    layout: LayoutMode,
+
+    /// Writes a JSON source map to the given path,
+    /// which maps every object emitted by an instruction back to its source line.
+    #[arg(long, value_name = "PATH")]
+    source_map: Option<PathBuf>,
+
+    /// Prints how many ticks every routine takes,
+    /// including the shortest and longest paths through the routines it spawns.
+    #[arg(long)]
+    timing_report: bool,
+
+    /// Writes the timing report as JSON to the given path.
+    #[arg(long, value_name = "PATH")]
+    timing_json: Option<PathBuf>,
+
+    /// Does not report the given lint. Use `warnings` for every lint.
+    #[arg(long, value_name = "LINT")]
+    allow: Vec<String>,
+
+    /// Reports the given lint as a warning. Use `warnings` for every lint.
+    #[arg(long, value_name = "LINT")]
+    warn: Vec<String>,
+
+    /// Reports the given lint as an error, which fails compilation. Use `warnings` for every lint.
+    #[arg(long, value_name = "LINT")]
+    deny: Vec<String>,
+
+    /// How errors and warnings are printed: human or json.
+    /// The JSON format prints one object per line, followed by a summary, and disables other logs.
+    #[arg(long, default_value = "human", value_name = "FORMAT")]
+    message_format: MessageFormat,
+
     /// Ending counter ID of memory block. Does not apply to programs using new memory.
     #[arg(long, default_value_t = 9999i16, value_parser = clap::value_parser!(i16))]
     mem_end_counter: i16,
@@ -77,7 +137,7 @@ struct Args {
     clipboard: bool,
 }
 
-fn get_obj_str(obj: &Vec<GDObject>) -> String {
+fn get_obj_str(obj: &[GDObject]) -> String {
     obj.iter()
         .map(|obj| obj.serialise_to_string())
         .collect::<Vec<_>>()
@@ -102,7 +162,7 @@ fn use_wslive(mut level: Level, port: u16) -> Result<(), Error> {
         objects_str
     );
 
-    socket.send(Message::Text(payload.into()))?;
+    socket.send(Message::Text(payload))?;
     let _ = socket.close(None);
 
     Ok(())
@@ -123,7 +183,7 @@ fn get_local_levels_path() -> Option<PathBuf> {
 }
 
 fn export_to_savefile(level: Level, logs_enabled: bool) -> Result<(), Error> {
-    if let None = get_local_levels_path() {
+    if get_local_levels_path().is_none() {
         log!(logs_enabled, "Unable to find savefile. Please pass --gmd.");
         return Ok(());
     }
@@ -135,24 +195,48 @@ fn export_to_savefile(level: Level, logs_enabled: bool) -> Result<(), Error> {
     Ok(())
 }
 
-fn main() -> Result<(), Error> {
+/// Prints an error in the arguments of the compiler, which does not point at the source.
+fn print_args_error(args: &Args, etype: TasmErrorType, details: String) {
+    if args.message_format == MessageFormat::Json {
+        let error = TasmError {
+            etype,
+            file: args.infile.clone(),
+            routine: String::new(),
+            error: true,
+            line: 0,
+            span: None,
+            details,
+            help: None,
+        };
+        print_json(&args.infile, &[error], &[]);
+    } else {
+        log!(!args.no_log, "{details}");
+    }
+}
+
+fn main() -> Result<ExitCode, Error> {
     let args = Args::parse();
-    log!(!args.no_log, "Parsing tasm...");
+    let json = args.message_format == MessageFormat::Json;
+    // other logs would get in the way of reading JSON line by line
+    let logs = !args.no_log && !json;
+    log!(logs, "Parsing tasm...");
     let file = fs::read_to_string(&args.infile)?;
 
     let id_limit = 9999;
     if args.mem_end_counter > id_limit {
-        log!(
-            !args.no_log,
-            "You may not set the end counter beyond the ID limit of {id_limit}"
+        print_args_error(
+            &args,
+            TasmErrorType::InvalidMemoryRange,
+            format!("You may not set the end counter beyond the ID limit of {id_limit}"),
         );
-        return Ok(());
+        return Ok(ExitCode::FAILURE);
     } else if args.mem_end_counter < 0 {
-        log!(
-            !args.no_log,
-            "You may not set the end counter to a negative ID."
+        print_args_error(
+            &args,
+            TasmErrorType::InvalidMemoryRange,
+            "You may not set the end counter to a negative ID.".into(),
         );
-        return Ok(());
+        return Ok(ExitCode::FAILURE);
     }
 
     let mut tasm = match lexer::parse_file(
@@ -160,71 +244,126 @@ fn main() -> Result<(), Error> {
         args.infile.clone(),
         args.mem_end_counter,
         args.group_offset,
-        args.verbose_logs && !args.no_log,
+        args.verbose_logs && logs,
         true,
         args.no_entry_point,
     ) {
         Ok(t) => t,
         Err(es) => {
-            if !args.no_log {
+            if json {
+                print_json(&args.infile, &es, &[]);
+            } else if logs {
                 print_errors(es, &format!("Unable to compile {}", &args.infile));
             }
-            return Ok(());
+            return Ok(ExitCode::FAILURE);
         }
     };
 
     tasm.release_mode = args.release;
+    tasm.debug_mode = args.debug;
+    tasm.trace_mode = args.trace;
+    tasm.annotate = args.annotate;
+    tasm.layout = args.layout;
+
+    // later levels override earlier ones, so denying always wins
+    let lint_levels = [
+        (&args.allow, LintLevel::Allow),
+        (&args.warn, LintLevel::Warn),
+        (&args.deny, LintLevel::Deny),
+    ];
+    for (names, level) in lint_levels {
+        for name in names {
+            if let Err(e) = tasm.lints.set(name, level) {
+                print_args_error(&args, TasmErrorType::UnknownLint, e);
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
 
     let level_name = match args.level_name {
         Some(l) => l,
         None => args.infile,
     };
 
-    log!(
-        !args.no_log,
-        "Using groups {} - {}",
-        args.group_offset + 1,
-        tasm.curr_group
-    );
-
-    log!(!args.no_log, "Encoding level...");
+    log!(logs, "Encoding level...");
 
     let level = match tasm.handle_routines(&level_name) {
         Err(e) => {
-            if !args.no_log {
+            if json {
+                print_json(&tasm.fname, &e, &tasm.warnings);
+            } else if logs {
                 print_errors(e, "Unable to compile to level");
+                print_warnings(&tasm.warnings);
             }
-            return Ok(());
+            return Ok(ExitCode::FAILURE);
         }
         Ok(l) => l,
     };
 
+    // groups are only known once the level is encoded
+    log!(
+        logs,
+        "Using groups {} - {}",
+        args.group_offset + 1,
+        tasm.curr_group - 1
+    );
+    if tasm.groups_saved > 0 {
+        log!(
+            logs,
+            "Saved {} groups by sharing auxiliary groups",
+            tasm.groups_saved
+        );
+    }
+
+    if json {
+        print_json(&tasm.fname, &[], &tasm.warnings);
+    } else if logs {
+        print_warnings(&tasm.warnings);
+    }
+
+    if let Some(path) = &args.source_map {
+        fs::write(path, source_map_json(&tasm.fname, &tasm.source_map))?;
+        log!(logs, "Wrote source map to {}", path.display());
+    }
+
+    if args.timing_report || args.timing_json.is_some() {
+        let reports = analyse(&tasm.timing);
+        // the table would get in the way of reading JSON line by line, --timing-json is used instead
+        if args.timing_report && !json {
+            print!("{}", timing_table(&reports));
+        }
+        if let Some(path) = &args.timing_json {
+            fs::write(path, timing_json(&tasm.fname, &reports))?;
+            log!(logs, "Wrote timing report to {}", path.display());
+        }
+    }
+
     if args.no_export {
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
 
     if args.clipboard {
         let mut ctx = ClipboardContext::new().unwrap();
         let obj_str = get_obj_str(&level.get_decrypted_data().unwrap().objects);
         ctx.set_contents(obj_str).unwrap();
-        log!(!args.no_log, "Sent to clipboard");
-        return Ok(());
+        log!(logs, "Sent to clipboard");
+        return Ok(ExitCode::SUCCESS);
     }
 
     match args.wslive {
         Some(port) => match use_wslive(level, port) {
-            Ok(()) => log!(!args.no_log, "Sent to WSLive"),
-            Err(e) => log!(!args.no_log, "Failed to send to WSLive: {}", e),
+            Ok(()) => log!(logs, "Sent to WSLive"),
+            Err(e) => log!(logs, "Failed to send to WSLive: {}", e),
         },
         None => match args.gmd {
             true => level.export_to_gmd(format!("{}.gmd", level_name))?,
             false => {
-                if let Err(e) = export_to_savefile(level, !args.no_log) {
-                    log!(!args.no_log, "Unable to export to savefile: {e}")
+                if let Err(e) = export_to_savefile(level, logs) {
+                    log!(logs, "Unable to export to savefile: {e}")
                 }
             }
         },
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
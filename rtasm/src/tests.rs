@@ -1,6 +1,11 @@
 use paste::paste;
 use std::time::Instant;
 
+use gdlib::gdobj::{
+    GDValue,
+    ids::{objects::TRIGGER_SPAWN, properties::TARGET_ITEM},
+};
+
 use crate::core::structs::{TasmPrimitive, TasmValue, TasmValueType, fits_arg_signature};
 
 use super::*;
@@ -65,6 +70,36 @@ macro_rules! tasm_test {
             }
         }
     };
+    // fail in translation stage with a specific error
+    ($file:literal, false, compile, $etype:ident) => {
+        paste! {
+            #[test]
+            fn [<translate_fail _ $file>]() {
+                let mut res = lexer::parse_file(
+                    fs::read_to_string(format!("../tests/{}.tasm", $file)).unwrap(),
+                    format!("testfile {}", $file),
+                    9999,
+                    0,
+                    true,
+                    true,
+                    false
+                ).unwrap();
+                let errors = res.handle_routines("").err().unwrap();
+                // later errors may follow from the first one
+                assert!(matches!(errors[0].etype, core::error::TasmErrorType::$etype), "{errors:?}")
+            }
+        }
+    };
+    // fail in lexing stage with a specific error, after the arms above so `compile` is not taken for one
+    ($file:literal, false, $etype:ident) => {
+        paste! {
+            #[test]
+            fn [<fileparse_fail _ $file>]() {
+                let errors = parse_fixture($file).err().unwrap();
+                assert!(matches!(errors[0].etype, core::error::TasmErrorType::$etype), "{errors:?}")
+            }
+        }
+    };
     // file in the `example_programs` directory
     ($file:literal, example) => {
         paste! {
@@ -115,6 +150,32 @@ macro_rules! tasm_test {
         }
     };
 
+    // compiled with the options that `$setup` sets on the parsed program, like debug mode
+    ($file:literal, $mode:ident, |$tasm:ident| $setup:expr) => {
+        paste! {
+            #[test]
+            fn [<$mode _ $file>]() {
+                let mut $tasm = lexer::parse_file(
+                    fs::read_to_string(format!("../tests/{}.tasm", $file)).unwrap(),
+                    format!("testfile {}", $file),
+                    9999,
+                    0,
+                    true,
+                    true,
+                    false
+                ).unwrap();
+                $setup;
+                match $tasm.handle_routines("") {
+                    Ok(_) => return,
+                    Err(e) => {
+                        print_errors(e, "errors");
+                        panic!()
+                    }
+                }
+            }
+        }
+    };
+
     // tests compiler-defined implementations located in `tests/compdef_{ident}.tasm`
     ($file:literal, compdef) => {
         paste! {
@@ -154,6 +215,81 @@ macro_rules! tasm_test {
     };
 }
 
+/// Parses `tests/{name}.tasm`, which must have an entry point.
+fn parse_fixture(name: &str) -> Result<core::structs::Tasm, Vec<core::error::TasmError>> {
+    lexer::parse_file(
+        fs::read_to_string(format!("../tests/{name}.tasm")).unwrap(),
+        format!("{name}.tasm"),
+        9999,
+        0,
+        true,
+        true,
+        false,
+    )
+}
+
+/// Compiled fixture, along with the objects of its level.
+struct Compiled {
+    tasm: core::structs::Tasm,
+    objects: Vec<GDObject>,
+}
+
+impl Compiled {
+    fn new(name: &str) -> Self {
+        Self::with(name, |_| ())
+    }
+
+    /// Compiles the fixture after `setup` sets the compiler options.
+    fn with(name: &str, setup: impl FnOnce(&mut core::structs::Tasm)) -> Self {
+        let mut tasm = parse_fixture(name).unwrap();
+        setup(&mut tasm);
+        let mut level = tasm.handle_routines("").unwrap();
+        let objects = level.get_decrypted_data_ref().unwrap().objects.clone();
+        Self { tasm, objects }
+    }
+
+    /// Objects emitted by the instruction on `line`, starting from 1.
+    fn on_line(&self, line: usize) -> Vec<&GDObject> {
+        self.tasm
+            .source_map
+            .iter()
+            .filter(|e| e.line + 1 == line)
+            .map(|e| &self.objects[e.index])
+            .collect()
+    }
+
+    fn group(&self, routine: &str) -> i16 {
+        self.tasm.routine_group_map[routine]
+    }
+
+    /// Group spawned by the spawn trigger on `group`, like the auxiliary groups of events.
+    fn spawned_by(&self, group: i16) -> Option<i16> {
+        self.objects
+            .iter()
+            .find(|o| o.id == TRIGGER_SPAWN && o.config.groups.iter().any(|g| g.id() == group))
+            .and_then(|o| id_property(o, TARGET_ITEM))
+    }
+}
+
+/// Line, starting from 1, and type of every error of a fixture that parses but does not compile.
+fn compile_errors(name: &str) -> Vec<(usize, core::error::TasmErrorType)> {
+    let errors = parse_fixture(name)
+        .unwrap()
+        .handle_routines("")
+        .err()
+        .unwrap();
+    errors.iter().map(|e| (e.line + 1, e.etype)).collect()
+}
+
+/// Group or item ID in a property, however it is stored.
+fn id_property(obj: &GDObject, property: u16) -> Option<i16> {
+    match obj.get_property(property)? {
+        GDValue::Group(id) | GDValue::Item(id) | GDValue::Short(id) => Some(id),
+        GDValue::Int(id) => Some(id as i16),
+        _ => None,
+    }
+}
+
 tasm_test!("fetch", example_no_entry_point);
 tasm_test!("fib_in_memory", example);
 tasm_test!("incrementer", example);
@@ -172,34 +308,86 @@ tasm_test!("bad_args", false);
 tasm_test!("bad_assignment", false, compile);
 tasm_test!("bad_instruction", false);
 tasm_test!("bad_token", false);
+tasm_test!("collisions", true);
+tasm_test!("compare_flag", false, BadFlag);
+tasm_test!("console", true);
 tasm_test!("concurrent", true);
 tasm_test!("correct", true);
 tasm_test!("division", true);
 tasm_test!("empty", false);
 tasm_test!("flags", true);
+tasm_test!("framebuffer", true);
 tasm_test!("init_rtn_mem", false);
 tasm_test!("init_spawn", false);
+tasm_test!("input", true);
 tasm_test!("lowercase", true);
 tasm_test!("multiple_mem", false, compile);
 tasm_test!("multiple_routines", false);
 tasm_test!("negative_ids", false);
 tasm_test!("no_entry_point", false);
 tasm_test!("no_memory", false, compile);
+tasm_test!("oncount", true);
+tasm_test!("onevent", true);
 tasm_test!("recursive", true);
 tasm_test!("remap_alias", true);
+tasm_test!("sequence", true);
+tasm_test!("sequence_trailing_entry", false, InvalidArguments);
+tasm_test!("sound", true);
+tasm_test!("sound_args", false, InvalidInstruction);
 tasm_test!("tab_spacing", true);
 tasm_test!("timer_not_counter", false);
 tasm_test!("timerops", true);
 tasm_test!("trailing_comma", false);
 tasm_test!("values", true);
+tasm_test!("weighted_empty_key", false, InvalidArguments);
+tasm_test!("weighted_random", true);
 // compdef: internal compiler-defined implementation
 tasm_test!("swap", compdef);
 tasm_test!("min", compdef);
 tasm_test!("max", compdef);
+// debug mode
+tasm_test!("breakpoint", debug, |tasm| tasm.debug_mode = true);
+tasm_test!("breakpoint", trace, |tasm| {
+    tasm.debug_mode = true;
+    tasm.trace_mode = true
+});
+// annotations
+tasm_test!("all_instructions", annotate, |tasm| tasm.annotate = true);
 // stdlib
 tasm_test!("mem_8bit", stdlib);
 tasm_test!("mem_14bit", stdlib);
 
+#[test]
+fn debug_counters() {
+    let errors = |trace| {
+        let mut tasm = parse_fixture("debug_counters").unwrap();
+        tasm.debug_mode = true;
+        tasm.trace_mode = trace;
+        tasm.handle_routines("")
+            .err()
+            .unwrap()
+            .iter()
+            .map(|e| (format!("{:?}", e.etype), e.line + 1, e.details.clone()))
+            .collect::<Vec<_>>()
+    };
+    let overlap = |line, details: &str| ("OverlappingItems".to_string(), line, details.to_string());
+
+    assert_eq!(
+        errors(false),
+        [overlap(
+            3,
+            "C8999 is also used by the breakpoint indicator."
+        )]
+    );
+    assert_eq!(
+        errors(true),
+        [
+            overlap(3, "C8999 is also used by the breakpoint indicator."),
+            overlap(4, "C8997 is also used by the trace panel."),
+        ]
+    );
+}
+
 #[test]
 fn int_detection() {
     assert!(fits_arg_signature(
@@ -252,3 +440,1059 @@ fn parse_tasm() -> anyhow::Result<()> {
     // level.export_to_gmd("test.gmd")?;
     Ok(())
 }
+
+#[test]
+fn source_map() {
+    let mut tasm = parse_fixture("breakpoint").unwrap();
+    tasm.handle_routines("").unwrap();
+
+    // ADD C1, 1 on line 6 of the _start routine
+    let add = tasm
+        .source_map
+        .iter()
+        .find(|e| e.routine == "_start")
+        .unwrap();
+    assert_eq!((add.instruction.as_str(), add.line + 1), ("ADD", 6));
+
+    let json = core::sourcemap::source_map_json(&tasm.fname, &tasm.source_map);
+    assert!(json.contains(r#""routine": "_start", "instruction": "ADD", "line": 6"#));
+}
+
+#[test]
+fn dead_routines() {
+    let parse = || parse_fixture("dead_routine").unwrap();
+
+    // warned about in normal mode
+    let mut tasm = parse();
+    tasm.handle_routines("").unwrap();
+    let warned = tasm
+        .warnings
+        .iter()
+        .map(|w| w.routine.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(warned, ["unused"]);
+
+    // removed in release mode, and still warned about
+    let mut tasm = parse();
+    tasm.release_mode = true;
+    tasm.handle_routines("").unwrap();
+    let warned = tasm
+        .warnings
+        .iter()
+        .map(|w| w.routine.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(warned, ["unused"]);
+    assert!(
+        tasm.warnings[0]
+            .details
+            .ends_with("Its group ID stays reserved.")
+    );
+    assert!(!tasm.routines.iter().any(|r| r.ident == "unused"));
+    assert!(tasm.routines.iter().any(|r| r.ident == "api"));
+}
+
+#[test]
+fn peephole() {
+    let mut tasm = parse_fixture("peephole").unwrap();
+    let routine = &mut tasm.routines[0];
+    core::peephole::optimise(routine);
+
+    let instrs = routine
+        .instructions
+        .iter()
+        .map(|i| {
+            let args = i
+                .args
+                .iter()
+                .map(|a| match a {
+                    TasmValue::Counter(c) => format!("C{c}"),
+                    TasmValue::Number(n) => n.to_string(),
+                    other => format!("{other:?}"),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{} {args}", i.ident).trim_end().to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        instrs,
+        [
+            "ADD C1, 3",
+            "WAIT 3",
+            "MOV C3, 7",
+            "WAIT 3",
+            "NOP",
+            "ADD C5, 1"
+        ]
+    );
+
+    tasm.release_mode = true;
+    assert!(tasm.handle_routines("").is_ok());
+}
+
+#[test]
+fn shared_groups() {
+    let mut tasm = parse_fixture("shared_groups").unwrap();
+    tasm.handle_routines("").unwrap();
+
+    // one auxiliary group per spawned routine, after the 3 routine groups
+    assert_eq!(tasm.spawn_groups.len(), 2);
+    assert_eq!(tasm.curr_group, 4 + 2);
+    assert_eq!(tasm.groups_saved, 5);
+}
+
+#[test]
+fn scheduler() {
+    let mut tasm = parse_fixture("schedule").unwrap();
+    let routine = &mut tasm.routines[0];
+    assert_eq!(core::scheduler::schedule(routine), 3);
+
+    let concurrent = routine
+        .instructions
+        .iter()
+        .map(|i| i.is_concurrent)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        concurrent,
+        [
+            false, true, true, false, true, false, false, false, true, false
+        ]
+    );
+}
+
+#[test]
+fn timing_report() {
+    let mut tasm = parse_fixture("timing").unwrap();
+    tasm.handle_routines("").unwrap();
+
+    let reports = core::timing::analyse(&tasm.timing)
+        .into_iter()
+        .map(|r| (r.routine, (r.length, r.best, r.worst)))
+        .collect::<std::collections::HashMap<_, _>>();
+    assert_eq!(reports["short"], (1, Some(1), Some(1)));
+    assert_eq!(reports["long"], (5, Some(5), Some(5)));
+    // the compare spawns its routine 2 ticks after it is executed
+    assert_eq!(reports["fork"], (2, Some(3), Some(7)));
+    assert_eq!(reports["loop"], (2, Some(2), None));
+    // SPAWN starts the fork on tick 2, and the compare may spawn long on tick 4
+    assert_eq!(reports["_start"], (3, Some(5), Some(9)));
+
+    // how instructions spawn their routines comes from their instruction type
+    use crate::{
+        core::timing::{InstrTiming, Spawns},
+        instr::INSTR_SPEC,
+    };
+    let timing = |ident: &str, args: &[TasmValue]| {
+        let t = InstrTiming::new(INSTR_SPEC[ident].2, args, 0, 1);
+        (t.spawns, t.spawn_delay)
+    };
+    let g = TasmValue::Group;
+    assert_eq!(
+        timing("ISRANDW", &[TasmValue::Dict(vec![(1, 1), (2, 3)])]),
+        (Spawns::OneOf(vec![1, 2]), 1)
+    );
+    assert_eq!(
+        timing("SRAND", &[g(1), TasmValue::Number(0.5)]),
+        (Spawns::Maybe(vec![1]), 2)
+    );
+    // INPUT is not an instant instruction, despite its name
+    assert_eq!(
+        timing("INPUT", &[g(1), TasmValue::String("key".into())]).0,
+        Spawns::None
+    );
+}
+
+#[test]
+fn data_races() {
+    let mut tasm = parse_fixture("data_race").unwrap();
+    tasm.handle_routines("").unwrap();
+
+    let races = tasm
+        .warnings
+        .iter()
+        .filter(|w| matches!(w.etype, core::error::TasmErrorType::DataRace))
+        .map(|w| (w.routine.as_str(), w.line + 1))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        races,
+        [
+            ("reader", 10),
+            ("then", 13),
+            ("otherwise", 16),
+            ("_start", 21),
+        ]
+    );
+}
+
+#[test]
+fn concurrent_chains() {
+    let mut tasm = parse_fixture("concurrent_chain").unwrap();
+    tasm.handle_routines("").unwrap();
+
+    let chains = tasm
+        .warnings
+        .iter()
+        .filter(|w| matches!(w.etype, core::error::TasmErrorType::ConcurrentChain))
+        .map(|w| w.line + 1)
+        .collect::<Vec<_>>();
+    // every instruction of the unsafe swap writes something another one reads
+    assert_eq!(chains, [7, 7, 7, 16, 19]);
+}
+
+#[test]
+fn layouts() {
+    let compile = |layout: &str| {
+        let mut tasm = parse_fixture("layout").unwrap();
+        tasm.layout = layout.parse().unwrap();
+        let mut level = tasm.handle_routines("").unwrap();
+        let (max_x, max_y) = level
+            .get_decrypted_data_ref()
+            .unwrap()
+            .objects
+            .iter()
+            .fold((f64::MIN, f64::MIN), |(x, y), o| {
+                (x.max(o.config.pos.0), y.max(o.config.pos.1))
+            });
+        let reports = core::timing::analyse(&tasm.timing)
+            .into_iter()
+            .map(|r| (r.routine, r.length, r.best, r.worst))
+            .collect::<Vec<_>>();
+        (reports, tasm.curr_group, max_x, max_y)
+    };
+
+    let (linear, linear_groups, linear_x, _) = compile("linear");
+    let (wrapped, wrapped_groups, wrapped_x, wrapped_y) = compile("wrapped:2");
+    let (grid, grid_groups, _, grid_y) = compile("grid:2");
+    // rows are continued on the tick they would have started on a single line
+    assert_eq!(wrapped, linear);
+    assert_eq!(grid, linear);
+    // counter is split into 3 rows, since the concurrent ADD stays on the first one
+    assert_eq!(wrapped_groups, linear_groups + 2);
+    assert_eq!(grid_groups, linear_groups + 2);
+    assert!(wrapped_x < linear_x);
+    assert!(grid_y < wrapped_y);
+
+    assert!("wrapped".parse::<core::layout::LayoutMode>().is_err());
+    assert!("grid:0".parse::<core::layout::LayoutMode>().is_err());
+}
+
+#[test]
+fn error_columns() {
+    let errors = parse_fixture("error_columns").err().unwrap();
+
+    let spans = errors
+        .iter()
+        .filter_map(|e| {
+            let span = e.span.as_ref()?;
+            Some((
+                e.line + 1,
+                span.column(),
+                &span.source[span.start..span.end],
+            ))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        [
+            (3, 5, "FROBNICATE"),
+            // the bad literal, and the argset that cannot be parsed or handled because of it
+            (4, 13, "0xZZ"),
+            (4, 9, "C1, 0xZZ"),
+            (4, 9, "C1, 0xZZ"),
+            // tabs count as a single column
+            (5, 14, "mode:bogus"),
+            (6, 14, ","),
+            (7, 13, "boom"),
+            // the argument that fits no argset, or the misspelled name
+            (8, 13, "é"),
+            (9, 16, "--nope"),
+            (10, 11, "mian"),
+        ]
+    );
+
+    let rendered = errors[0].render(false);
+    assert!(rendered.ends_with("3 |     FROBNICATE C1\n  |     ^^^^^^^^^^"));
+}
+
+#[test]
+fn lints() {
+    let compile = |levels: &[(&str, core::lint::LintLevel)]| {
+        let mut tasm = parse_fixture("lints").unwrap();
+        for (name, level) in levels {
+            tasm.lints.set(name, *level).unwrap();
+        }
+        let lines = |es: &[core::error::TasmError]| {
+            es.iter()
+                .map(|e| (format!("{:?}", e.etype), e.line + 1))
+                .collect::<Vec<_>>()
+        };
+        match tasm.handle_routines("") {
+            Ok(_) => (lines(&tasm.warnings), vec![]),
+            Err(es) => (lines(&tasm.warnings), lines(&es)),
+        }
+    };
+    let lint = |etype: &str, line| (etype.to_string(), line);
+
+    let (warnings, errors) = compile(&[]);
+    assert_eq!(
+        warnings,
+        [
+            lint("UnusedAlias", 4),
+            lint("EmptyRoutineSpawn", 17),
+            // the worker allows shadowed counters
+            lint("ShadowedCounter", 19),
+            lint("DataRace", 19),
+            lint("UnusedDisplay", 6),
+        ]
+    );
+    assert!(errors.is_empty());
+
+    use crate::core::lint::LintLevel::{Allow, Deny};
+    let (warnings, errors) = compile(&[("warnings", Deny), ("data_race", Allow)]);
+    assert!(warnings.is_empty());
+    assert_eq!(errors.len(), 4);
+
+    let mut lints = core::lint::LintConfig::default();
+    assert!(lints.set("nope", Deny).is_err());
+    assert!(
+        lexer::parse_file(
+            "main:\n    NOP ; tasm: allow(nope)",
+            "".into(),
+            9999,
+            0,
+            true,
+            true,
+            true
+        )
+        .unwrap()
+        .warnings
+        .iter()
+        .any(|w| matches!(w.etype, core::error::TasmErrorType::BadPragma))
+    );
+}
+
+#[test]
+fn json_diagnostics() {
+    use crate::core::diagnostics::{diagnostic_json, summary_json};
+
+    let errors = parse_fixture("error_columns").err().unwrap();
+    let trailing_comma = errors
+        .iter()
+        .find(|e| matches!(e.etype, core::error::TasmErrorType::TrailingComma))
+        .unwrap();
+    assert_eq!(
+        diagnostic_json(trailing_comma),
+        r#"{"kind": "diagnostic", "type": "TrailingComma", "severity": "error", "lint": null, "file": "error_columns.tasm", "routine": "main", "line": 6, "column": 14, "end_column": 15, "message": "Trailing commas are not allowed.", "help": "Remove the trailing comma."}"#
+    );
+    assert_eq!(
+        summary_json("error_columns.tasm", errors.len(), 0),
+        r#"{"kind": "summary", "file": "error_columns.tasm", "success": false, "errors": 10, "warnings": 0}"#
+    );
+
+    // the first line is still a line, and bad tokens are not in any routine
+    let errors = parse_fixture("bad_token_first_line").err().unwrap();
+    assert_eq!(
+        diagnostic_json(&errors[0]),
+        r#"{"kind": "diagnostic", "type": "BadToken", "severity": "error", "lint": null, "file": "bad_token_first_line.tasm", "routine": null, "line": 1, "column": 1, "end_column": 10, "message": "Bad token.", "help": null}"#
+    );
+
+    let mut tasm = parse_fixture("lints").unwrap();
+    tasm.handle_routines("").unwrap();
+    // lint warnings are tagged with the name of their lint
+    let unused_alias = diagnostic_json(&tasm.warnings[0]);
+    assert!(unused_alias.contains(r#""severity": "warning", "lint": "unused_alias""#));
+    assert!(unused_alias.contains(r#""help": "Remove the alias.""#));
+    // every line is a single JSON object
+    assert!(
+        tasm.warnings
+            .iter()
+            .map(diagnostic_json)
+            .all(|d| !d.contains('\n') && d.starts_with('{') && d.ends_with('}'))
+    );
+}
+
+#[test]
+fn suggestions() {
+    use crate::core::suggest::{closest, edit_distance};
+
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    // swapped characters count as a single edit
+    assert_eq!(edit_distance("jmup", "jump"), 1);
+    assert_eq!(closest("Delay", ["delay", "relay"]), Some("delay"));
+    assert_eq!(closest("xyz", ["delay"]), None);
+    assert_eq!(closest("x", [""]), None);
+
+    let errors = parse_fixture("suggestions").err().unwrap();
+    let helps = errors
+        .iter()
+        .map(|e| (e.line + 1, e.help.as_deref().unwrap_or_default()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        helps,
+        [
+            (
+                8,
+                "wroker is not a routine or alias. Did you mean worker? SPAWN takes <group>."
+            ),
+            (
+                9,
+                "scroe is not a routine or alias. Did you mean score? ADD takes one of: <item>, <item>; <item>, <number>; <item>, <item>, <item>."
+            ),
+            (10, "Did you mean delay?"),
+            (11, "Did you mean jump?"),
+            (12, "Did you mean SPAWN?"),
+            (13, "Did you mean delay?"),
+            // the implicit routine before the first label is never suggested
+            (14, "MOV takes one of: <item>, <number>; <item>, <item>."),
+        ]
+    );
+}
+
+#[test]
+fn oncount() {
+    use gdlib::gdobj::ids::{
+        objects::TRIGGER_COUNT,
+        properties::{COUNT_TRIGGER_MULTI_ACTIVATE, INPUT_ITEM_1, TARGET_COUNT},
+    };
+
+    use crate::{
+        core::error::TasmErrorType::{BadFlag, InvalidArguments},
+        instr::ids::{INSTANT_COUNT_MODE, InstantCountMode, TRIGGER_INSTANT_COUNT},
+    };
+
+    let compiled = Compiled::new("oncount");
+
+    // every event spawns its routine through the auxiliary group of that routine
+    let spawns = |line| {
+        let event = compiled.on_line(line)[0];
+        compiled.spawned_by(id_property(event, TARGET_ITEM).unwrap())
+    };
+    assert_eq!(spawns(3), Some(compiled.group("reached_ten")));
+    assert_eq!(spawns(4), Some(compiled.group("negative")));
+    assert_eq!(spawns(15), Some(compiled.group("reached_ten")));
+
+    // listening events wait for C1 to be 10, and keep listening with the multi flag
+    let count = compiled.on_line(3)[0];
+    assert_eq!(count.id, TRIGGER_COUNT);
+    assert_eq!(id_property(count, INPUT_ITEM_1), Some(1));
+    assert_eq!(count.get_property(TARGET_COUNT), Some(GDValue::Int(10)));
+    assert_eq!(
+        count.get_property(COUNT_TRIGGER_MULTI_ACTIVATE),
+        Some(GDValue::Bool(true))
+    );
+
+    // instant events compare once, with the operator of the op flag
+    for (line, mode) in [
+        (4, InstantCountMode::Smaller),
+        (15, InstantCountMode::Equals),
+    ] {
+        let instant = compiled.on_line(line)[0];
+        assert_eq!(instant.id, TRIGGER_INSTANT_COUNT);
+        assert_eq!(
+            instant.get_property(INSTANT_COUNT_MODE),
+            Some(GDValue::Int(mode as i32))
+        );
+    }
+
+    // only counters can be listened to, and only instant events take =, > or <
+    assert_eq!(
+        compile_errors("oncount_errors"),
+        [(3, InvalidArguments), (4, BadFlag), (5, BadFlag)]
+    );
+}
+
+#[test]
+fn collisions() {
+    use gdlib::gdobj::ids::{
+        objects::{COLLISION_BLOCK, TRIGGER_COLLISION},
+        properties::{
+            CONTROLLING_PLAYER_1, CONTROLLING_PLAYER_2, CONTROLLING_TARGET_PLAYER, INPUT_ITEM_1,
+            INPUT_ITEM_2, TRIGGER_ON_EXIT,
+        },
+    };
+
+    use crate::core::error::TasmErrorType::{BadFlag, BadID};
+
+    let compiled = Compiled::new("collisions");
+
+    // the block keeps its collision ID, and the group that moves it
+    let block = compiled.on_line(3)[0];
+    assert_eq!(block.id, COLLISION_BLOCK);
+    assert_eq!(id_property(block, INPUT_ITEM_1), Some(1));
+    assert!(block.config.groups.iter().any(|g| g.id() == 500));
+
+    // a player takes the place of the first block, and both players collide without blocks
+    let bool_props = [
+        CONTROLLING_PLAYER_1,
+        CONTROLLING_PLAYER_2,
+        CONTROLLING_TARGET_PLAYER,
+        TRIGGER_ON_EXIT,
+    ];
+    for (line, blocks, flags, routine) in [
+        (5, (1, 2), [false, false, false, false], "hit"),
+        (6, (1, 2), [false, false, false, true], "left"),
+        (7, (0, 2), [false, true, false, false], "hit"),
+        (16, (0, 0), [false, false, true, false], "hit"),
+        (17, (0, 1), [true, true, false, true], "left"),
+    ] {
+        let trigger = compiled.on_line(line)[0];
+        assert_eq!(trigger.id, TRIGGER_COLLISION);
+        assert_eq!(
+            (
+                id_property(trigger, INPUT_ITEM_1),
+                id_property(trigger, INPUT_ITEM_2)
+            ),
+            (Some(blocks.0), Some(blocks.1)),
+            "line {line}"
+        );
+        for (prop, flag) in bool_props.into_iter().zip(flags) {
+            assert_eq!(
+                trigger.get_property(prop),
+                Some(GDValue::Bool(flag)),
+                "line {line}"
+            );
+        }
+        let aux = id_property(trigger, TARGET_ITEM).unwrap();
+        assert_eq!(compiled.spawned_by(aux), Some(compiled.group(routine)));
+    }
+
+    assert_eq!(
+        compile_errors("collision_errors"),
+        [(3, BadID), (4, BadFlag), (5, BadID), (6, BadID)]
+    );
+}
+
+#[test]
+fn sound() {
+    use gdlib::gdobj::ids::{
+        objects::TRIGGER_SONG,
+        properties::{FADE_IN_TIME_IN_MS, FADE_OUT_TIME_IN_MS, LOOP_SONG, SONG_CHANNEL, SONG_ID},
+    };
+
+    use crate::instr::ids::{
+        SFX_IS_UNIQUE, SFX_PITCH, SFX_UNIQUE_ID, SOUND_STOP, TRIGGER_EDIT_SFX, TRIGGER_EDIT_SONG,
+        TRIGGER_SFX,
+    };
+
+    let compiled = Compiled::new("sound");
+
+    // fades are given in seconds and stored in milliseconds
+    let song = compiled.on_line(3)[0];
+    assert_eq!(song.id, TRIGGER_SONG);
+    assert_eq!(song.get_property(SONG_ID), Some(GDValue::Int(467339)));
+    assert_eq!(song.get_property(LOOP_SONG), Some(GDValue::Bool(true)));
+    assert_eq!(
+        song.get_property(FADE_IN_TIME_IN_MS),
+        Some(GDValue::Int(2500))
+    );
+
+    let sfx = compiled.on_line(6)[0];
+    assert_eq!(sfx.id, TRIGGER_SFX);
+    assert_eq!(sfx.get_property(SFX_IS_UNIQUE), Some(GDValue::Bool(true)));
+    assert_eq!(
+        sfx.get_property(FADE_OUT_TIME_IN_MS),
+        Some(GDValue::Int(500))
+    );
+
+    // only sounds with a uid can be stopped, so the others are not unique
+    let pitched = compiled.on_line(7)[0];
+    assert_eq!(pitched.get_property(SFX_PITCH), Some(GDValue::Float(-3.5)));
+    assert_eq!(pitched.get_property(SFX_IS_UNIQUE), None);
+    assert_eq!(pitched.get_property(SFX_UNIQUE_ID), None);
+
+    // every stop trigger targets what the matching play trigger started:
+    // the uid of a sound effect, or the channel of a song, which defaults to 0
+    for (line, id, property, started) in [
+        (
+            9,
+            TRIGGER_EDIT_SFX,
+            SFX_UNIQUE_ID,
+            sfx.get_property(SFX_UNIQUE_ID),
+        ),
+        (
+            10,
+            TRIGGER_EDIT_SONG,
+            SONG_CHANNEL,
+            song.get_property(SONG_CHANNEL),
+        ),
+        (11, TRIGGER_EDIT_SONG, SONG_CHANNEL, Some(GDValue::Int(0))),
+    ] {
+        let stop = compiled.on_line(line)[0];
+        assert_eq!(stop.id, id, "line {line}");
+        assert!(started.is_some(), "line {line}");
+        assert_eq!(stop.get_property(property), started, "line {line}");
+        assert_eq!(
+            stop.get_property(SOUND_STOP),
+            Some(GDValue::Bool(true)),
+            "line {line}"
+        );
+    }
+}
+
+#[test]
+fn weighted_random() {
+    use crate::core::error::TasmErrorType::InvalidArguments;
+    use gdlib::gdobj::ids::{
+        objects::TRIGGER_ADVANCED_RANDOM, properties::RANDOM_PROBABILITIES_LIST,
+    };
+
+    let compiled = Compiled::new("weighted_random");
+    let outcomes_on = |line| {
+        let random = compiled.on_line(line)[0];
+        assert_eq!(random.id, TRIGGER_ADVANCED_RANDOM);
+        match random.get_property(RANDOM_PROBABILITIES_LIST) {
+            Some(GDValue::ProbabilitiesList(outcomes)) => outcomes.to_vec(),
+            _ => panic!("{random:?}"),
+        }
+    };
+    let [heads, tails, edge] = ["heads", "tails", "edge"].map(|r| compiled.group(r));
+
+    // every outcome spawns its target through an auxiliary group with its weight,
+    // and a target is either a routine or a group
+    for (line, expected) in [
+        (3, vec![(heads, 10), (tails, 30), (edge, 60)]),
+        (5, vec![(1, 3), (heads, 5)]),
+    ] {
+        let outcomes = outcomes_on(line);
+        assert_eq!(outcomes.len(), expected.len(), "line {line}");
+        for ((aux, weight), (target, expected_weight)) in outcomes.into_iter().zip(expected) {
+            assert_eq!(weight, expected_weight, "line {line}");
+            assert_eq!(compiled.spawned_by(aux), Some(target), "line {line}");
+        }
+    }
+
+    // the instant form picks between the routine groups themselves,
+    // and an outcome may have a weight of 0 as long as another does not
+    assert_eq!(outcomes_on(4), [(heads, 1), (tails, 1)]);
+    assert_eq!(outcomes_on(6), [(tails, 2), (edge, 0)]);
+
+    // the empty dict is only reported by the instruction, and not as a bad argument
+    let errors = parse_fixture("weighted_errors")
+        .unwrap()
+        .handle_routines("")
+        .err()
+        .unwrap();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| (e.line + 1, e.etype, e.details.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                3,
+                InvalidArguments,
+                "At least one outcome weight must be larger than 0."
+            ),
+            (
+                4,
+                InvalidArguments,
+                "Weighted random spawns need at least one outcome."
+            ),
+            (5, InvalidArguments, "Outcome weights can not be negative."),
+        ]
+    );
+}
+
+#[test]
+fn sequence() {
+    use gdlib::gdobj::ids::objects::TRIGGER_RESET_GROUP;
+
+    use crate::{
+        core::error::TasmErrorType::InvalidArguments,
+        instr::ids::{
+            SEQUENCE_MIN_INTERVAL, SEQUENCE_MODE, SEQUENCE_RESET, SEQUENCE_STEP_RESET,
+            SEQUENCE_STEPS, SequenceMode, TRIGGER_SEQUENCE,
+        },
+    };
+
+    let compiled = Compiled::new("sequence");
+    let steps_on = |line| {
+        let sequence = compiled.on_line(line)[0];
+        assert_eq!(sequence.id, TRIGGER_SEQUENCE);
+        let Some(GDValue::ProbabilitiesList(steps)) = sequence.get_property(SEQUENCE_STEPS) else {
+            panic!("{sequence:?}")
+        };
+        steps.to_vec()
+    };
+    let spawned = |steps: &[(i16, i32)]| {
+        steps
+            .iter()
+            .map(|&(aux, count)| (compiled.spawned_by(aux).unwrap(), count))
+            .collect::<Vec<_>>()
+    };
+    let [red, green, blue] = ["red", "green", "blue"].map(|r| compiled.group(r));
+
+    // a list of routines takes one step each, and a dict a number of steps each
+    assert_eq!(spawned(&steps_on(9)), [(red, 1), (green, 1), (blue, 1)]);
+    assert_eq!(spawned(&steps_on(12)), [(red, 2), (green, 1)]);
+
+    // a routine that is stepped to twice is spawned through the same auxiliary group
+    let repeated = steps_on(24);
+    assert_eq!(spawned(&repeated), [(blue, 1), (red, 1), (blue, 1)]);
+    assert_eq!(repeated[0].0, repeated[2].0);
+
+    for (line, mode, interval, reset, step_reset) in [
+        (9, SequenceMode::Loop, 0.1, 0.0, false),
+        (12, SequenceMode::Last, 0.0, 1.5, true),
+        (24, SequenceMode::Stop, 0.0, 0.0, false),
+    ] {
+        let sequence = compiled.on_line(line)[0];
+        assert_eq!(
+            sequence.get_property(SEQUENCE_MODE),
+            Some(GDValue::Int(mode as i32)),
+            "line {line}"
+        );
+        assert_eq!(
+            sequence.get_property(SEQUENCE_MIN_INTERVAL),
+            Some(GDValue::Float(interval)),
+            "line {line}"
+        );
+        assert_eq!(
+            sequence.get_property(SEQUENCE_RESET),
+            Some(GDValue::Float(reset)),
+            "line {line}"
+        );
+        assert_eq!(
+            sequence.get_property(SEQUENCE_STEP_RESET),
+            Some(GDValue::Bool(step_reset)),
+            "line {line}"
+        );
+    }
+
+    // SEQRESET resets the routine group of the sequence
+    let reset = compiled.on_line(6)[0];
+    assert_eq!(reset.id, TRIGGER_RESET_GROUP);
+    assert_eq!(
+        id_property(reset, TARGET_ITEM),
+        Some(compiled.group("step"))
+    );
+
+    assert_eq!(
+        compile_errors("sequence_errors"),
+        [3, 4, 5, 6, 7].map(|line| (line, InvalidArguments))
+    );
+}
+
+#[test]
+fn onevent() {
+    use gdlib::gdobj::{
+        ids::{
+            objects::TRIGGER_EVENT,
+            properties::{EVENT_EXTRA_ID_2, EVENT_LISTENERS},
+        },
+        triggers::ExtraID2,
+    };
+
+    use crate::{core::error::TasmErrorType::UnknownEvent, instr::events::EVENT_NAMES};
+
+    let compiled = Compiled::new("onevent");
+
+    // event names can stand for several events, and the player flags pick the player,
+    // where setting both is the same as setting neither
+    for (line, name, player, routine) in [
+        (3, "jump", ExtraID2::All, "on_jump"),
+        (4, "landing", ExtraID2::P2, "on_land"),
+        (5, "portal", ExtraID2::All, "on_portal"),
+        (6, "orb", ExtraID2::P1, "on_jump"),
+        (7, "pad", ExtraID2::All, "on_jump"),
+    ] {
+        let event = compiled.on_line(line)[0];
+        assert_eq!(event.id, TRIGGER_EVENT, "line {line}");
+        assert_eq!(
+            event.get_property(EVENT_LISTENERS),
+            Some(GDValue::Events(EVENT_NAMES[name].to_vec())),
+            "line {line}"
+        );
+        assert_eq!(
+            event.get_property(EVENT_EXTRA_ID_2),
+            Some(GDValue::Int(player as i32)),
+            "line {line}"
+        );
+        let aux = id_property(event, TARGET_ITEM).unwrap();
+        assert_eq!(
+            compiled.spawned_by(aux),
+            Some(compiled.group(routine)),
+            "line {line}"
+        );
+    }
+    assert_eq!(EVENT_NAMES["landing"].len(), 5);
+
+    // unknown names are reported when parsing, with the closest name if there is one
+    let errors = parse_fixture("unknown_event").err().unwrap();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| (e.line + 1, e.etype, e.help.as_deref()))
+            .collect::<Vec<_>>(),
+        [
+            (3, UnknownEvent, None),
+            (4, UnknownEvent, Some("Did you mean landing?")),
+        ]
+    );
+}
+
+#[test]
+fn console() {
+    use gdlib::gdobj::ids::{
+        objects::TRIGGER_ITEM_EDIT,
+        properties::{INPUT_ITEM_1, MODIFIER},
+    };
+
+    use crate::core::error::TasmErrorType::{
+        InvalidArguments, MultipleConsoleInstances, NonexistentConsoleAccess, OverlappingItems,
+    };
+
+    let compiled = Compiled::new("console");
+    let info = compiled.tasm.console_info.as_ref().unwrap();
+    // 2x4 cells from C9000, followed by the cursor and the register
+    assert_eq!(info.items, 9000..=9009);
+    let register = *info.items.end();
+
+    // PRINT copies its value to the register, then writes the register to the cursor
+    for (line, input, modifier) in [(7, Some(1), None), (8, None, Some(GDValue::Float(7.0)))] {
+        let print = compiled.on_line(line);
+        assert_eq!(print[0].id, TRIGGER_ITEM_EDIT, "line {line}");
+        assert_eq!(
+            id_property(print[0], TARGET_ITEM),
+            Some(register),
+            "line {line}"
+        );
+        assert_eq!(id_property(print[0], INPUT_ITEM_1), input, "line {line}");
+        if modifier.is_some() {
+            assert_eq!(print[0].get_property(MODIFIER), modifier, "line {line}");
+        }
+        assert_eq!(print[1].id, TRIGGER_SPAWN, "line {line}");
+        assert_eq!(
+            id_property(print[1], TARGET_ITEM),
+            Some(info.write_group),
+            "line {line}"
+        );
+    }
+
+    // PRINTLN and CLS only spawn the group that does the work
+    for (line, group) in [(9, info.newline_group), (11, info.clear_group)] {
+        let spawn = compiled.on_line(line)[0];
+        assert_eq!(spawn.id, TRIGGER_SPAWN, "line {line}");
+        assert_eq!(id_property(spawn, TARGET_ITEM), Some(group), "line {line}");
+    }
+
+    assert_eq!(
+        compile_errors("console_errors"),
+        [
+            (3, InvalidArguments),
+            (4, InvalidArguments),
+            (6, MultipleConsoleInstances)
+        ]
+    );
+    assert_eq!(
+        compile_errors("no_console"),
+        [(3, NonexistentConsoleAccess)]
+    );
+    // the overlapping console is not created, so printing to it fails as well
+    assert_eq!(
+        compile_errors("console_overlap"),
+        [(4, OverlappingItems), (7, NonexistentConsoleAccess)]
+    );
+}
+
+#[test]
+fn framebuffer() {
+    use gdlib::gdobj::ids::{
+        objects::{TRIGGER_ITEM_EDIT, TRIGGER_TOGGLE},
+        properties::{ACTIVATE_GROUP, INPUT_ITEM_1, MODIFIER},
+    };
+
+    use crate::core::error::TasmErrorType::{
+        InvalidArguments, MultipleFramebufferInstances, NonexistentFramebufferAccess,
+        OverlappingItems,
+    };
+
+    let compiled = Compiled::new("framebuffer");
+    let info = compiled.tasm.fb_info.as_ref().unwrap();
+
+    // PLOT copies the coordinates to the registers, then spawns the controller
+    let plot = compiled.on_line(7);
+    for (obj, reg) in plot.iter().zip([info.x_reg, info.y_reg]) {
+        assert_eq!(obj.id, TRIGGER_ITEM_EDIT);
+        assert_eq!(id_property(obj, TARGET_ITEM), Some(reg));
+        assert_eq!(id_property(obj, INPUT_ITEM_1), Some(1));
+    }
+    assert!(plot.iter().any(
+        |o| o.id == TRIGGER_SPAWN && id_property(o, TARGET_ITEM) == Some(info.controller_group)
+    ));
+
+    // literal coordinates are set directly
+    let literal = compiled.on_line(10);
+    assert_eq!(literal[0].get_property(MODIFIER), Some(GDValue::Float(3.0)));
+    assert_eq!(literal[1].get_property(MODIFIER), Some(GDValue::Float(0.0)));
+
+    // the mode picks the triggers of the pixel that are left on
+    let toggle_target = |line| {
+        compiled
+            .on_line(line)
+            .into_iter()
+            .find(|o| o.id == TRIGGER_TOGGLE)
+            .and_then(|o| id_property(o, TARGET_ITEM))
+    };
+    assert_eq!(toggle_target(9), Some(info.off_group));
+    assert_eq!(toggle_target(11), Some(info.on_group));
+
+    // CLEAR hides every pixel
+    let clear = compiled.on_line(12)[0];
+    assert_eq!(clear.id, TRIGGER_TOGGLE);
+    assert_eq!(id_property(clear, TARGET_ITEM), Some(info.pixels_group));
+    assert_eq!(
+        clear.get_property(ACTIVATE_GROUP),
+        Some(GDValue::Bool(false))
+    );
+
+    assert_eq!(
+        compile_errors("framebuffer_errors"),
+        [
+            (3, InvalidArguments),
+            (4, InvalidArguments),
+            (5, InvalidArguments),
+            (7, MultipleFramebufferInstances),
+            (10, InvalidArguments)
+        ]
+    );
+    // the overlapping framebuffer is not created, so plotting to it fails as well
+    assert_eq!(
+        compile_errors("framebuffer_overlap"),
+        [(4, OverlappingItems), (8, NonexistentFramebufferAccess)]
+    );
+
+    // the error names the instruction, since CLEAR does not plot
+    let errors = parse_fixture("no_framebuffer")
+        .unwrap()
+        .handle_routines("")
+        .err()
+        .unwrap();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| (e.line + 1, e.etype, e.details.as_str()))
+            .collect::<Vec<_>>(),
+        [(
+            3,
+            NonexistentFramebufferAccess,
+            "Cannot use CLEAR when no framebuffer exists."
+        )]
+    );
+}
+
+#[test]
+fn input() {
+    use gdlib::gdobj::ids::{
+        objects::TRIGGER_ITEM_EDIT,
+        properties::{INPUT_ITEM_1, MODIFIER},
+    };
+
+    use crate::core::error::TasmErrorType::InvalidInstruction;
+
+    let compiled = Compiled::new("input");
+    let keypad = compiled.on_line(3);
+    let in_group = |group: i16| {
+        keypad
+            .iter()
+            .filter(move |o| o.config.groups.iter().any(|g| g.id() == group))
+    };
+
+    // every digit key spawns a group that appends its digit to C1
+    let digit_groups = keypad
+        .iter()
+        .filter(|o| o.id == TRIGGER_SPAWN && o.config.groups.is_empty())
+        .filter_map(|o| id_property(o, TARGET_ITEM))
+        .filter(|&g| in_group(g).next().is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(digit_groups.len(), 10);
+    for (digit, &group) in digit_groups.iter().enumerate() {
+        let edits = in_group(group).collect::<Vec<_>>();
+        assert!(
+            edits
+                .iter()
+                .all(|o| o.id == TRIGGER_ITEM_EDIT && id_property(o, TARGET_ITEM) == Some(1))
+        );
+        assert_eq!(edits[0].get_property(MODIFIER), Some(GDValue::Float(10.0)));
+        assert_eq!(
+            edits[1].get_property(MODIFIER),
+            Some(GDValue::Float(digit as f64))
+        );
+    }
+
+    // the backspace key divides C1 by 10 in place, and is the only edit outside of the digit groups
+    let backspace = keypad
+        .iter()
+        .filter(|o| o.id == TRIGGER_ITEM_EDIT && o.config.groups.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(backspace.len(), 1);
+    assert_eq!(id_property(backspace[0], INPUT_ITEM_1), Some(1));
+    assert_eq!(id_property(backspace[0], TARGET_ITEM), Some(1));
+    assert_eq!(
+        backspace[0].get_property(MODIFIER),
+        Some(GDValue::Float(10.0))
+    );
+
+    // the enter key spawns the routine
+    let add = compiled.group("add");
+    assert!(
+        keypad
+            .iter()
+            .any(|o| o.id == TRIGGER_SPAWN && id_property(o, TARGET_ITEM) == Some(add))
+    );
+
+    // keypads are stacked right below the IOBlock row at y = 75, or below the trace panel
+    let keys_y = |compiled: &Compiled, line| {
+        compiled
+            .on_line(line)
+            .iter()
+            .find(|o| o.id == TRIGGER_SPAWN && o.config.groups.is_empty())
+            .map(|o| o.config.pos.1)
+            .unwrap()
+    };
+    assert_eq!(keys_y(&compiled, 3), 15.0);
+    assert_eq!(keys_y(&compiled, 4), -45.0);
+    let traced = Compiled::with("input", |tasm| {
+        tasm.debug_mode = true;
+        tasm.trace_mode = true;
+    });
+    // the last row of the trace panel is at y = -15 - 30 * routines
+    let last_trace_row = -15.0 - 30.0 * traced.tasm.routines.len() as f64;
+    assert_eq!(keys_y(&traced, 3), last_trace_row - 60.0);
+
+    // keypads can only be placed in _init, and can only enter into items
+    let errors = parse_fixture("input_errors").err().unwrap();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| (e.line + 1, e.etype))
+            .collect::<Vec<_>>(),
+        [(3, InvalidInstruction), (6, InvalidInstruction)]
+    );
+    assert!(errors[1].details.contains("exclusive"), "{errors:?}");
+}
+
+#[test]
+fn dict_parsing() {
+    use std::collections::HashMap;
+
+    use crate::core::flags::parse_dict;
+
+    // the empty routine name is the text before the first label
+    let groups = HashMap::from([("".to_string(), 1), ("red".to_string(), 2)]);
+    let aliases = HashMap::from([("r".to_string(), "red".to_string())]);
+    let parse = |value| parse_dict(value, &groups, &aliases);
+
+    assert_eq!(parse("[red, r, g5]"), Some(vec![(2, 1), (2, 1), (5, 1)]));
+    assert_eq!(parse("[]"), Some(vec![]));
+    assert_eq!(parse("[ ]"), Some(vec![]));
+    assert_eq!(parse("[red,]"), None);
+    assert_eq!(parse("[, red]"), None);
+    assert_eq!(parse("[red, , red]"), None);
+
+    assert_eq!(parse("{red: 2, g5: 0x10}"), Some(vec![(2, 2), (5, 16)]));
+    assert_eq!(parse("{}"), Some(vec![]));
+    assert_eq!(parse("{: 5}"), None);
+    assert_eq!(parse("{red: }"), None);
+    assert_eq!(parse("{red: 1,}"), None);
+}